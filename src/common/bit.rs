@@ -110,6 +110,145 @@ impl Bit {
         }
     }
 
+    /// Returns the index of the first 0 bit of a byte array where the 0-th bit is the most significant bit
+    /// of the first byte in array, or `None` if every bit is 1.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let i = Bit::first_zero_bit(&[0b11111111_u8, 0b11010000_u8]); // Some(10)
+    /// let i = Bit::first_zero_bit(&[0b11111111_u8, 0b11111111_u8]); // None
+    /// ```
+    pub fn first_zero_bit(v: &[u8]) -> Option<u32> {
+        v.iter()
+            .position(|b| *b != u8::MAX)
+            .map(|i| i as u32 * 8 + v[i].leading_ones())
+    }
+
+    /// Returns the index of the first run of `len` consecutive 0 bits of a byte array where the 0-th bit
+    /// is the most significant bit of the first byte in array, or `None` if there is no such run.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let i = Bit::first_zero_run(&[0b10011000_u8, 0b01111111_u8], 3); // Some(5)
+    /// let i = Bit::first_zero_run(&[0b10011000_u8, 0b01111111_u8], 5); // None
+    /// ```
+    pub fn first_zero_run(v: &[u8], len: u32) -> Option<u32> {
+        if len == 0 {
+            return Some(0);
+        }
+
+        // the current run of 0 bits is `[start, start + run)`
+        let (mut start, mut run) = (0_u32, 0_u32);
+        for (i, b) in v.iter().enumerate() {
+            match *b {
+                u8::MAX => run = 0,
+                0 => {
+                    if run == 0 {
+                        start = i as u32 * 8;
+                    }
+                    run += 8;
+                }
+                b => {
+                    for j in 0..8_u32 {
+                        if b & (0b10000000_u8 >> j) != 0 {
+                            run = 0;
+                            continue;
+                        }
+                        if run == 0 {
+                            start = i as u32 * 8 + j;
+                        }
+                        run += 1;
+                        if run >= len {
+                            return Some(start);
+                        }
+                    }
+                }
+            }
+            if run >= len {
+                return Some(start);
+            }
+        }
+
+        None
+    }
+
+    /// Set every bit in the range `[start, end)` of a byte array to 1, where the 0-th bit is the most
+    /// significant bit of the first byte in array.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let mut buf = [0b00000000_u8, 0b00000000_u8];
+    /// Bit::set_range(&mut buf, 6, 11); // [0b00000011_u8, 0b11100000_u8]
+    /// ```
+    pub fn set_range(v: &mut [u8], start: u32, end: u32) -> Result<()> {
+        Bit::fill_range(v, start, end, Bit::One)
+    }
+
+    /// Set every bit in the range `[start, end)` of a byte array to 0, where the 0-th bit is the most
+    /// significant bit of the first byte in array.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let mut buf = [0b11111111_u8, 0b11111111_u8];
+    /// Bit::clear_range(&mut buf, 6, 11); // [0b11111100_u8, 0b00011111_u8]
+    /// ```
+    pub fn clear_range(v: &mut [u8], start: u32, end: u32) -> Result<()> {
+        Bit::fill_range(v, start, end, Bit::Zero)
+    }
+
+    /// Set every bit in the range `[start, end)` of a byte array, a whole byte at a time.
+    fn fill_range(v: &mut [u8], start: u32, end: u32, bit: Bit) -> Result<()> {
+        if start > end || end > (v.len() * 8) as u32 {
+            return Err(anyhow!(
+                "IllegalArgumentError: bytes.length = {}; range = {}..{}",
+                v.len(),
+                start,
+                end
+            ));
+        }
+
+        let mut i = start;
+        while i < end {
+            let offset = i % 8;
+            // number of bits to fill within the current byte
+            let n = (8 - offset).min(end - i);
+            // n bits of one starting from the `offset`-th most significant bit
+            let mask = ((0xFF_u16 << (8 - n)) as u8) >> offset;
+
+            let b = &mut v[(i / 8) as usize];
+            match bit {
+                Bit::Zero => *b &= !mask,
+                Bit::One => *b |= mask,
+            }
+            i += n;
+        }
+
+        Ok(())
+    }
+
+    /// Returns an iterator over the indices of the 1 bits of a byte array in ascending order, where the
+    /// 0-th bit is the most significant bit of the first byte in array.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let ones: Vec<u32> = Bit::iter_ones(&[0b10000001_u8, 0b00000000_u8, 0b01000000_u8]).collect(); // [0, 7, 17]
+    /// ```
+    pub fn iter_ones(v: &[u8]) -> impl Iterator<Item = u32> + '_ {
+        v.iter()
+            .enumerate()
+            .filter(|(_, b)| **b != 0)
+            .flat_map(|(i, b)| {
+                (0..8_u32)
+                    .filter(move |j| b & (0b10000000_u8 >> j) != 0)
+                    .map(move |j| i as u32 * 8 + j)
+            })
+    }
+
     /// Counts the number of 1 bit flag in byte array.
     ///
     /// # Example
@@ -262,6 +401,96 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_first_zero_bit() {
+        assert_eq!(
+            Some(0),
+            Bit::first_zero_bit(&[0b00000000_u8, 0b00000000_u8])
+        );
+        assert_eq!(
+            Some(3),
+            Bit::first_zero_bit(&[0b11101111_u8, 0b00000000_u8])
+        );
+        assert_eq!(
+            Some(7),
+            Bit::first_zero_bit(&[0b11111110_u8, 0b00000000_u8])
+        );
+        assert_eq!(
+            Some(8),
+            Bit::first_zero_bit(&[0b11111111_u8, 0b01111111_u8])
+        );
+        assert_eq!(
+            Some(15),
+            Bit::first_zero_bit(&[0b11111111_u8, 0b11111110_u8])
+        );
+        assert_eq!(None, Bit::first_zero_bit(&[0b11111111_u8, 0b11111111_u8]));
+        assert_eq!(None, Bit::first_zero_bit(&[]));
+    }
+
+    #[test]
+    fn test_first_zero_run() {
+        let v = [0b10011000_u8, 0b01111111_u8, 0b11000000_u8, 0b00000000_u8];
+
+        assert_eq!(Some(0), Bit::first_zero_run(&v, 0));
+        assert_eq!(Some(1), Bit::first_zero_run(&v, 1));
+        assert_eq!(Some(1), Bit::first_zero_run(&v, 2));
+        assert_eq!(Some(5), Bit::first_zero_run(&v, 3));
+        assert_eq!(Some(5), Bit::first_zero_run(&v, 4));
+        assert_eq!(Some(18), Bit::first_zero_run(&v, 5));
+        assert_eq!(Some(18), Bit::first_zero_run(&v, 14));
+        assert_eq!(None, Bit::first_zero_run(&v, 15));
+        assert_eq!(Some(0), Bit::first_zero_run(&[0_u8; 2], 16));
+        assert_eq!(None, Bit::first_zero_run(&[0b11111111_u8; 2], 1));
+        assert_eq!(None, Bit::first_zero_run(&[], 1));
+    }
+
+    #[test]
+    fn test_set_range() -> Result<()> {
+        let mut v: [u8; 3] = [0b00000000, 0b00000000, 0b00000000];
+
+        Bit::set_range(&mut v, 2, 5)?;
+        assert_eq!([0b00111000_u8, 0b00000000_u8, 0b00000000_u8], v);
+        Bit::set_range(&mut v, 6, 17)?;
+        assert_eq!([0b00111011_u8, 0b11111111_u8, 0b10000000_u8], v);
+        Bit::set_range(&mut v, 20, 20)?;
+        assert_eq!([0b00111011_u8, 0b11111111_u8, 0b10000000_u8], v);
+        Bit::set_range(&mut v, 0, 24)?;
+        assert_eq!([0b11111111_u8, 0b11111111_u8, 0b11111111_u8], v);
+
+        assert!(Bit::set_range(&mut v, 0, 25).is_err());
+        assert!(Bit::set_range(&mut v, 5, 4).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_clear_range() -> Result<()> {
+        let mut v: [u8; 3] = [0b11111111, 0b11111111, 0b11111111];
+
+        Bit::clear_range(&mut v, 2, 5)?;
+        assert_eq!([0b11000111_u8, 0b11111111_u8, 0b11111111_u8], v);
+        Bit::clear_range(&mut v, 6, 17)?;
+        assert_eq!([0b11000100_u8, 0b00000000_u8, 0b01111111_u8], v);
+        Bit::clear_range(&mut v, 23, 24)?;
+        assert_eq!([0b11000100_u8, 0b00000000_u8, 0b01111110_u8], v);
+        Bit::clear_range(&mut v, 0, 24)?;
+        assert_eq!([0b00000000_u8, 0b00000000_u8, 0b00000000_u8], v);
+
+        assert!(Bit::clear_range(&mut v, 24, 25).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_iter_ones() {
+        let ones: Vec<u32> =
+            Bit::iter_ones(&[0b10000001_u8, 0b00000000_u8, 0b01000110_u8]).collect();
+        assert_eq!(vec![0, 7, 17, 21, 22], ones);
+
+        assert_eq!(0, Bit::iter_ones(&[0b00000000_u8, 0b00000000_u8]).count());
+        assert_eq!(16, Bit::iter_ones(&[0b11111111_u8, 0b11111111_u8]).count());
+    }
+
     #[test]
     fn test_count_ones_on_byte() {
         assert_eq!(Bit::count_ones_u8(&0b11111111_u8), 8);
@@ -1,7 +1,11 @@
 use crate::common::Bit;
 use anyhow::{anyhow, Result};
 use byteorder::{BigEndian, ByteOrder, LittleEndian};
-use std::io::Write;
+use bytes::buf::UninitSlice;
+use bytes::{Buf, BufMut};
+use std::fmt::{Display, Formatter};
+use std::io::{Read, Write};
+use std::ops::Deref;
 
 /// An enum to represent the byte order of the `ByteBuffer`
 #[derive(Debug, Copy, Clone)]
@@ -300,6 +304,24 @@ impl ByteBuffer {
     /// Read a defined amount of raw bytes, or Return an error if not enough bytes are available.
     /// _Note_: This method resets the read and write cursor for bitwise reading.
     pub fn read_bytes(&mut self, size: usize) -> Result<Vec<u8>> {
+        let mut res = Vec::<u8>::with_capacity(size);
+        res.write_all(self.read_slice(size)?)?;
+        Ok(res)
+    }
+
+    /// Same as `read_bytes()` method but borrows the bytes from the buffer instead of copying them,
+    /// or return an error if not enough bytes are available.
+    /// _Note_: This method resets the read and write cursor for bitwise reading.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use buffer::*;
+    ///
+    /// let mut buf = ByteBuffer::from_bytes(&vec![0x1, 0x2, 0x3]);
+    /// let slice = buf.read_slice(2).unwrap(); // slice contains [0x1, 0x2]
+    /// ```
+    pub fn read_slice(&mut self, size: usize) -> Result<&[u8]> {
         self.flush_bit();
 
         if self.r_pos + size > self.data.len() {
@@ -307,10 +329,8 @@ impl ByteBuffer {
         }
 
         let range = self.r_pos..(self.r_pos + size);
-        let mut res = Vec::<u8>::new();
-        res.write_all(&self.data[range])?;
         self.r_pos += size;
-        Ok(res)
+        Ok(&self.data[range])
     }
 
     /// Read one byte, or return an error if not enough bytes are available.
@@ -438,20 +458,35 @@ impl ByteBuffer {
         todo!()
     }
 
+    /// Move the reading and writing cursors to the next byte boundary if a bitwise
+    /// read or write has left them in the middle of a byte.
     pub fn flush_bit(&mut self) {
-        todo!()
+        if self.r_bit > 0 {
+            self.flush_r_bit();
+        }
+        if self.w_bit > 0 {
+            self.flush_w_bit();
+        }
     }
 
-    fn flush_w_bit(&mut self) {}
+    fn flush_w_bit(&mut self) {
+        self.w_pos += 1;
+        self.w_bit = 0;
+    }
 
-    fn flush_r_bit(&mut self) {}
+    fn flush_r_bit(&mut self) {
+        self.r_pos += 1;
+        self.r_bit = 0;
+    }
 
-    pub fn to_string(&self) -> String {
-        todo!()
+    /// Returns the whole content of the buffer without copying it, regardless of the cursors.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.data
     }
 
+    /// Returns a copy of the whole content of the buffer, regardless of the cursors.
     pub fn to_bytes(&self) -> Vec<u8> {
-        todo!()
+        self.data.clone()
     }
 
     pub fn get_r_pos(&self) -> usize {
@@ -470,3 +505,224 @@ impl ByteBuffer {
         todo!()
     }
 }
+
+impl Deref for ByteBuffer {
+    type Target = [u8];
+
+    fn deref(&self) -> &Self::Target {
+        self.as_bytes()
+    }
+}
+
+/// Reads from the reading cursor, at most up to the end of the buffer.
+impl Read for ByteBuffer {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.flush_bit();
+
+        let size = buf.len().min(self.remaining());
+        buf[..size].copy_from_slice(&self.data[self.r_pos..(self.r_pos + size)]);
+        self.r_pos += size;
+        Ok(size)
+    }
+}
+
+/// Writes at the writing cursor, the buffer is automatically extended if needed.
+impl Write for ByteBuffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.write_bytes(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Reads from the reading cursor.
+///
+/// _Note_: The `get_*` methods of `Buf` always use their own byte order and ignore `endian()`,
+///         use the `read_*` methods to honor the byte order of the buffer.
+impl Buf for ByteBuffer {
+    fn remaining(&self) -> usize {
+        self.data.len().saturating_sub(self.r_pos)
+    }
+
+    fn chunk(&self) -> &[u8] {
+        &self.data[self.r_pos.min(self.data.len())..]
+    }
+
+    fn advance(&mut self, cnt: usize) {
+        self.flush_bit();
+
+        assert!(
+            cnt <= self.remaining(),
+            "cannot advance past `remaining`: {:?} <= {:?}",
+            cnt,
+            self.remaining()
+        );
+        self.r_pos += cnt;
+    }
+}
+
+/// Writes at the writing cursor, the buffer is automatically extended if needed.
+///
+/// _Note_: The `put_*` methods of `BufMut` always use their own byte order and ignore `endian()`,
+///         use the `write_*` methods to honor the byte order of the buffer.
+unsafe impl BufMut for ByteBuffer {
+    fn remaining_mut(&self) -> usize {
+        isize::MAX as usize - self.w_pos
+    }
+
+    unsafe fn advance_mut(&mut self, cnt: usize) {
+        self.flush_bit();
+
+        self.w_pos += cnt;
+        if self.w_pos > self.data.len() {
+            // SAFETY: the caller has initialized the bytes up to `w_pos`,
+            // either in place or in the spare capacity returned by `chunk_mut()`.
+            self.data.set_len(self.w_pos);
+        }
+    }
+
+    fn chunk_mut(&mut self) -> &mut UninitSlice {
+        self.flush_bit();
+
+        if self.w_pos < self.data.len() {
+            // overwrite the bytes behind the writing cursor first
+            let len = self.data.len();
+            UninitSlice::new(&mut self.data[self.w_pos..len])
+        } else {
+            if self.data.capacity() == self.data.len() {
+                self.data.reserve(64);
+            }
+            UninitSlice::uninit(self.data.spare_capacity_mut())
+        }
+    }
+}
+
+/// Formats the buffer content as space separated hex bytes, e.g. `0x01 0xff 0x45`
+impl Display for ByteBuffer {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut bytes = self.data.iter();
+        if let Some(b) = bytes.next() {
+            write!(f, "{:#04x}", b)?;
+        }
+        for b in bytes {
+            write!(f, " {:#04x}", b)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_slice() -> Result<()> {
+        let mut buf = ByteBuffer::from_bytes(&[0x1, 0x2, 0x3, 0x4, 0x5]);
+
+        assert_eq!(&[0x1_u8, 0x2], buf.read_slice(2)?);
+        assert_eq!(0x3, buf.read_u8()?);
+        assert_eq!(&[0x4_u8, 0x5], buf.read_slice(2)?);
+        assert!(buf.read_slice(1).is_err());
+        assert_eq!(&[] as &[u8], buf.read_slice(0)?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_bytes() -> Result<()> {
+        let mut buf = ByteBuffer::new();
+        buf.write_string("rookie");
+        buf.write_u16(7);
+
+        assert_eq!("rookie", buf.read_string()?);
+        assert_eq!(vec![0x0_u8, 0x7], buf.read_bytes(2)?);
+        assert!(buf.read_bytes(1).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_as_bytes() {
+        let mut buf = ByteBuffer::new();
+        buf.write_u32(0x01ff4500);
+
+        assert_eq!(&[0x01_u8, 0xff, 0x45, 0x00], buf.as_bytes());
+        assert_eq!(vec![0x01_u8, 0xff, 0x45, 0x00], buf.to_bytes());
+        assert_eq!(4, buf.len());
+        assert_eq!(&[0xff_u8, 0x45], &buf[1..3]);
+        assert!(buf.starts_with(&[0x01, 0xff]));
+    }
+
+    #[test]
+    fn test_read_write() -> std::io::Result<()> {
+        let mut buf = ByteBuffer::new();
+        buf.write_all(&[0x1, 0x2, 0x3])?;
+        write!(buf, "{}", 45)?;
+        assert_eq!(&[0x1_u8, 0x2, 0x3, b'4', b'5'], buf.as_bytes());
+
+        let mut dst = [0_u8; 4];
+        assert_eq!(4, buf.read(&mut dst)?);
+        assert_eq!([0x1_u8, 0x2, 0x3, b'4'], dst);
+        assert_eq!(1, buf.read(&mut dst)?);
+        assert_eq!(b'5', dst[0]);
+        assert_eq!(0, buf.read(&mut dst)?);
+
+        let mut buf = ByteBuffer::from_bytes(&[0x1, 0x2]);
+        let mut dst = vec![];
+        buf.read_to_end(&mut dst)?;
+        assert_eq!(vec![0x1_u8, 0x2], dst);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_buf() -> Result<()> {
+        let mut buf = ByteBuffer::from_bytes(&[0x0, 0x1, 0x2, 0x3, 0x4]);
+
+        assert_eq!(5, buf.remaining());
+        assert_eq!(0x0001, buf.get_u16());
+        assert_eq!(&[0x2_u8, 0x3, 0x4], buf.chunk());
+        buf.advance(1);
+        assert_eq!(0x3, buf.read_u8()?);
+        assert_eq!(1, buf.remaining());
+
+        let mut dst = [0_u8; 1];
+        buf.copy_to_slice(&mut dst);
+        assert_eq!([0x4_u8], dst);
+        assert!(!buf.has_remaining());
+        assert!(buf.chunk().is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_buf_mut() -> Result<()> {
+        let mut buf = ByteBuffer::new();
+        buf.put_u16(0x0102);
+        buf.put_slice(&[0x3; 100]);
+        assert_eq!(102, buf.len());
+        assert_eq!(0x0102, buf.read_u16()?);
+        assert_eq!(vec![0x3_u8; 100], buf.read_bytes(100)?);
+
+        // mixes with the writing cursor of `write_*` methods
+        let mut buf = ByteBuffer::new();
+        buf.write_u8(0x1);
+        buf.put_u8(0x2);
+        buf.write_u8(0x3);
+        assert_eq!(&[0x1_u8, 0x2, 0x3], buf.as_bytes());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_to_string() {
+        assert_eq!("", ByteBuffer::new().to_string());
+        assert_eq!(
+            "0x01 0xff 0x45",
+            ByteBuffer::from_bytes(&[0x1, 0xff, 0x45]).to_string()
+        );
+    }
+}
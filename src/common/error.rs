@@ -1,4 +1,4 @@
-use crate::databox::DataBox;
+use crate::databox::{DataBox, DataType};
 use thiserror::Error;
 
 #[derive(Error, Debug, PartialEq)]
@@ -11,4 +11,34 @@ pub enum DBError {
 
     #[error("Get bit in byte: index {0} out of bounds")]
     BitOutBoundError(u32),
+
+    #[error("Partition {0} is opened read-only")]
+    ReadOnlyError(usize),
+
+    #[error("Expected {0} values in record, got {1}")]
+    RecordSizeError(usize, usize),
+
+    #[error("Cannot cast {0:?} to {1}")]
+    CastError(DataBox, DataType),
+
+    #[error("Cannot cast {0:?} to {1} without losing information")]
+    LossyCastError(DataBox, DataType),
+
+    #[error("Unknown function {0}")]
+    UnknownFunctionError(String),
+
+    #[error("Function {0} does not take {1} arguments")]
+    ArityError(String, usize),
+
+    #[error("Function {0} already exists")]
+    DuplicateFunctionError(String),
+
+    #[error("Invalid option {0}: {1}")]
+    OptionError(&'static str, String),
+
+    #[error("Partition {0} has reached its quota of {1} pages")]
+    QuotaExceededError(usize, usize),
+
+    #[error("Invalid UTF-8 in string after {0} valid bytes")]
+    Utf8Error(usize),
 }
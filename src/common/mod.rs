@@ -2,5 +2,6 @@ mod bit;
 mod buffer;
 pub mod constant;
 pub mod error;
+pub mod options;
 
 pub use bit::*;
@@ -0,0 +1,313 @@
+use crate::common::constant::PAGE_SIZE;
+use crate::common::error::DBError;
+use anyhow::Result;
+use serde::Deserialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
+
+/// Page replacement policy of the buffer pool.
+#[derive(Copy, Clone, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EvictionPolicy {
+    Lru,
+    Clock,
+}
+
+/// Options of a database, validated when the database is opened.
+///
+/// Options are set through `DatabaseOptions::builder()`, optionally starting from a TOML file
+/// where every key is optional, e.g.
+///
+/// ```toml
+/// buffer_pool_size = 1024
+/// eviction_policy = "clock"
+/// wal_dir = "/var/lib/rookiedb/wal"
+/// lock_timeout_ms = 5000
+/// ```
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DatabaseOptions {
+    /// Number of frames in the buffer pool
+    buffer_pool_size: usize,
+    /// Size of a page in bytes
+    page_size: usize,
+    /// Page replacement policy of the buffer pool
+    eviction_policy: EvictionPolicy,
+    /// Directory of the write-ahead log, `None` to keep it in the database directory
+    wal_dir: Option<PathBuf>,
+    /// Time to wait for a lock before aborting, in milliseconds
+    lock_timeout_ms: u64,
+    /// Number of worker threads
+    worker_threads: usize,
+}
+
+impl Default for DatabaseOptions {
+    fn default() -> Self {
+        Self {
+            buffer_pool_size: 262_144,
+            page_size: PAGE_SIZE,
+            eviction_policy: EvictionPolicy::Lru,
+            wal_dir: None,
+            lock_timeout_ms: 10_000,
+            worker_threads: thread::available_parallelism().map_or(1, |n| n.get()),
+        }
+    }
+}
+
+impl DatabaseOptions {
+    /// Returns a builder starting from the default options.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let options = DatabaseOptions::builder()
+    ///     .buffer_pool_size(1024)
+    ///     .eviction_policy(EvictionPolicy::Clock)
+    ///     .build()?;
+    /// ```
+    pub fn builder() -> DatabaseOptionsBuilder {
+        DatabaseOptionsBuilder {
+            options: DatabaseOptions::default(),
+        }
+    }
+
+    /// Checks every option, and returns `DBError::OptionError` for the first invalid one.
+    pub fn validate(&self) -> Result<(), DBError> {
+        if self.buffer_pool_size == 0 {
+            return Err(DBError::OptionError(
+                "buffer_pool_size",
+                "must be at least 1 frame".to_string(),
+            ));
+        }
+        // the on-disk layout of partitions is fixed by `PAGE_SIZE` at compile time
+        if self.page_size != PAGE_SIZE {
+            return Err(DBError::OptionError(
+                "page_size",
+                format!("only {} is supported, got {}", PAGE_SIZE, self.page_size),
+            ));
+        }
+        if let Some(ref wal_dir) = self.wal_dir {
+            if wal_dir.as_os_str().is_empty() {
+                return Err(DBError::OptionError(
+                    "wal_dir",
+                    "must not be empty".to_string(),
+                ));
+            }
+        }
+        if self.lock_timeout_ms == 0 {
+            return Err(DBError::OptionError(
+                "lock_timeout_ms",
+                "must be positive".to_string(),
+            ));
+        }
+        if self.worker_threads == 0 {
+            return Err(DBError::OptionError(
+                "worker_threads",
+                "must be at least 1 thread".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn buffer_pool_size(&self) -> usize {
+        self.buffer_pool_size
+    }
+
+    pub fn page_size(&self) -> usize {
+        self.page_size
+    }
+
+    pub fn eviction_policy(&self) -> EvictionPolicy {
+        self.eviction_policy
+    }
+
+    pub fn wal_dir(&self) -> Option<&Path> {
+        self.wal_dir.as_deref()
+    }
+
+    pub fn lock_timeout(&self) -> Duration {
+        Duration::from_millis(self.lock_timeout_ms)
+    }
+
+    pub fn worker_threads(&self) -> usize {
+        self.worker_threads
+    }
+}
+
+/// Builder of `DatabaseOptions`, see `DatabaseOptions::builder()`.
+pub struct DatabaseOptionsBuilder {
+    options: DatabaseOptions,
+}
+
+impl DatabaseOptionsBuilder {
+    /// Returns a builder starting from the options of a TOML document, with defaults for missing keys.
+    pub fn from_toml(s: &str) -> Result<Self> {
+        Ok(Self {
+            options: toml::from_str(s)?,
+        })
+    }
+
+    /// Returns a builder starting from the options of a TOML file, with defaults for missing keys.
+    pub fn from_toml_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::from_toml(&fs::read_to_string(path)?)
+    }
+
+    pub fn buffer_pool_size(mut self, buffer_pool_size: usize) -> Self {
+        self.options.buffer_pool_size = buffer_pool_size;
+        self
+    }
+
+    pub fn page_size(mut self, page_size: usize) -> Self {
+        self.options.page_size = page_size;
+        self
+    }
+
+    pub fn eviction_policy(mut self, eviction_policy: EvictionPolicy) -> Self {
+        self.options.eviction_policy = eviction_policy;
+        self
+    }
+
+    pub fn wal_dir<P: Into<PathBuf>>(mut self, wal_dir: P) -> Self {
+        self.options.wal_dir = Some(wal_dir.into());
+        self
+    }
+
+    /// Sets the lock timeout, rounded up to a whole number of milliseconds so that a positive
+    /// timeout never becomes 0. A timeout too long for `u64` milliseconds is capped at `u64::MAX`.
+    pub fn lock_timeout(mut self, lock_timeout: Duration) -> Self {
+        let millis = lock_timeout.as_nanos().div_ceil(1_000_000);
+        self.options.lock_timeout_ms = u64::try_from(millis).unwrap_or(u64::MAX);
+        self
+    }
+
+    pub fn worker_threads(mut self, worker_threads: usize) -> Self {
+        self.options.worker_threads = worker_threads;
+        self
+    }
+
+    /// Validates and returns the options.
+    pub fn build(self) -> Result<DatabaseOptions> {
+        self.options.validate()?;
+        Ok(self.options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_builder() -> Result<()> {
+        let options = DatabaseOptions::builder().build()?;
+        assert_eq!(DatabaseOptions::default(), options);
+        assert_eq!(PAGE_SIZE, options.page_size());
+        assert_eq!(None, options.wal_dir());
+
+        let options = DatabaseOptions::builder()
+            .buffer_pool_size(16)
+            .eviction_policy(EvictionPolicy::Clock)
+            .wal_dir("wal")
+            .lock_timeout(Duration::from_secs(2))
+            .worker_threads(3)
+            .build()?;
+        assert_eq!(16, options.buffer_pool_size());
+        assert_eq!(EvictionPolicy::Clock, options.eviction_policy());
+        assert_eq!(Some(Path::new("wal")), options.wal_dir());
+        assert_eq!(Duration::from_secs(2), options.lock_timeout());
+        assert_eq!(3, options.worker_threads());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_lock_timeout_rounding() -> Result<()> {
+        let lock_timeout = |d: Duration| -> Result<Duration> {
+            Ok(DatabaseOptions::builder()
+                .lock_timeout(d)
+                .build()?
+                .lock_timeout())
+        };
+
+        assert_eq!(
+            Duration::from_millis(1),
+            lock_timeout(Duration::from_nanos(1))?
+        );
+        assert_eq!(
+            Duration::from_millis(1),
+            lock_timeout(Duration::from_micros(999))?
+        );
+        assert_eq!(
+            Duration::from_millis(2),
+            lock_timeout(Duration::from_micros(1001))?
+        );
+        assert_eq!(
+            Duration::from_millis(7),
+            lock_timeout(Duration::from_millis(7))?
+        );
+        assert_eq!(
+            Duration::from_millis(u64::MAX),
+            lock_timeout(Duration::MAX)?
+        );
+        assert!(lock_timeout(Duration::ZERO).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate() {
+        let invalid = |builder: DatabaseOptionsBuilder| match builder.build() {
+            Ok(_) => None,
+            Err(e) => match e.downcast_ref() {
+                Some(DBError::OptionError(name, _)) => Some(*name),
+                _ => None,
+            },
+        };
+
+        let builder = DatabaseOptions::builder;
+        assert_eq!(
+            Some("buffer_pool_size"),
+            invalid(builder().buffer_pool_size(0))
+        );
+        assert_eq!(Some("page_size"), invalid(builder().page_size(8192)));
+        assert_eq!(Some("wal_dir"), invalid(builder().wal_dir("")));
+        assert_eq!(
+            Some("lock_timeout_ms"),
+            invalid(builder().lock_timeout(Duration::ZERO))
+        );
+        assert_eq!(Some("worker_threads"), invalid(builder().worker_threads(0)));
+    }
+
+    #[test]
+    fn test_from_toml() -> Result<()> {
+        let options = DatabaseOptionsBuilder::from_toml(
+            r#"
+            buffer_pool_size = 1024
+            eviction_policy = "clock"
+            lock_timeout_ms = 500
+            "#,
+        )?
+        .worker_threads(2)
+        .build()?;
+        assert_eq!(1024, options.buffer_pool_size());
+        assert_eq!(EvictionPolicy::Clock, options.eviction_policy());
+        assert_eq!(Duration::from_millis(500), options.lock_timeout());
+        assert_eq!(2, options.worker_threads());
+        assert_eq!(PAGE_SIZE, options.page_size());
+
+        let dir = tempdir()?;
+        let path = dir.path().join("rookiedb.toml");
+        fs::write(&path, "wal_dir = \"/tmp/wal\"\nbuffer_pool_size = 0\n")?;
+        let builder = DatabaseOptionsBuilder::from_toml_file(&path)?;
+        assert!(builder.build().is_err());
+
+        assert!(DatabaseOptionsBuilder::from_toml("buffer_pool = 1").is_err());
+        assert!(DatabaseOptionsBuilder::from_toml("eviction_policy = \"fifo\"").is_err());
+        assert!(DatabaseOptionsBuilder::from_toml("buffer_pool_size = -1").is_err());
+        assert!(DatabaseOptionsBuilder::from_toml_file(dir.path().join("missing.toml")).is_err());
+
+        Ok(())
+    }
+}
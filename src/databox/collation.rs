@@ -0,0 +1,139 @@
+use crate::databox::DataBox;
+use std::cmp::Ordering;
+
+/// Ordering and equality of string values.
+#[derive(Copy, Clone, Debug, Default, Hash, PartialEq, Eq)]
+pub enum Collation {
+    /// Raw byte order, the order of `DataBox` itself
+    #[default]
+    Binary,
+    /// Strings are compared after lower-casing them, so `'ABC' = 'abc'`
+    CaseInsensitive,
+    /// Dictionary order, strings are compared case-insensitively first, and ties are broken by
+    /// binary order, so `'apple' < 'Banana' < 'banana'` while `'Banana' <> 'banana'`
+    Dictionary,
+}
+
+impl Collation {
+    /// Compares two strings under this collation.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let o = Collation::Binary.compare("B", "a"); // Ordering::Less
+    /// let o = Collation::CaseInsensitive.compare("B", "a"); // Ordering::Greater
+    /// let o = Collation::CaseInsensitive.compare("A", "a"); // Ordering::Equal
+    /// ```
+    pub fn compare(&self, a: &str, b: &str) -> Ordering {
+        match self {
+            Collation::Binary => a.cmp(b),
+            Collation::CaseInsensitive => a.to_lowercase().cmp(&b.to_lowercase()),
+            Collation::Dictionary => Collation::CaseInsensitive
+                .compare(a, b)
+                .then_with(|| a.cmp(b)),
+        }
+    }
+
+    /// Compares two values, strings under this collation and any other values as `DataBox` does.
+    pub fn compare_values(&self, a: &DataBox, b: &DataBox) -> Option<Ordering> {
+        match (a, b) {
+            (DataBox::String(a), DataBox::String(b)) => Some(self.compare(a, b)),
+            (a, b) => a.partial_cmp(b),
+        }
+    }
+
+    /// Returns `true` if two strings are equal under this collation.
+    pub fn equals(&self, a: &str, b: &str) -> bool {
+        self.compare(a, b) == Ordering::Equal
+    }
+
+    /// Returns a byte string for a string, such that comparing the byte strings of two strings gives
+    /// the same result as `compare()`, and equal strings under this collation have equal byte strings.
+    ///
+    /// _Note_: Sort keys are used for ordering and grouping, e.g. as the key of an index or of a hash
+    ///         table, and the string cannot be recovered from its sort key.
+    pub fn sort_key(&self, s: &str) -> Vec<u8> {
+        match self {
+            Collation::Binary => s.as_bytes().to_vec(),
+            Collation::CaseInsensitive => s.to_lowercase().into_bytes(),
+            Collation::Dictionary => {
+                // the case-insensitive key is terminated by two zero bytes, so that it is compared
+                // entirely before the binary key that breaks ties
+                let mut key = vec![];
+                for b in s.to_lowercase().bytes() {
+                    match b {
+                        0 => key.extend_from_slice(&[0x00, 0xFF]),
+                        b => key.push(b),
+                    }
+                }
+                key.extend_from_slice(&[0x00, 0x00]);
+                key.extend_from_slice(s.as_bytes());
+                key
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WORDS: [&str; 9] = ["", "a", "A", "a\0", "ab", "aB", "B", "b", "ba"];
+
+    #[test]
+    fn test_compare() {
+        assert_eq!(Ordering::Less, Collation::Binary.compare("B", "a"));
+        assert_eq!(
+            Ordering::Greater,
+            Collation::CaseInsensitive.compare("B", "a")
+        );
+        assert_eq!(Ordering::Greater, Collation::Dictionary.compare("B", "a"));
+
+        assert!(Collation::CaseInsensitive.equals("Straße", "STRAßE"));
+        assert!(!Collation::Binary.equals("abc", "ABC"));
+        assert!(!Collation::Dictionary.equals("abc", "ABC"));
+
+        let mut words = WORDS.to_vec();
+        words.sort_by(|a, b| Collation::Dictionary.compare(a, b));
+        assert_eq!(vec!["", "A", "a", "a\0", "aB", "ab", "B", "b", "ba"], words);
+    }
+
+    #[test]
+    fn test_compare_values() {
+        let ci = Collation::CaseInsensitive;
+        assert_eq!(
+            Some(Ordering::Equal),
+            ci.compare_values(&DataBox::from("X"), &DataBox::from("x"))
+        );
+        assert_eq!(
+            Some(Ordering::Less),
+            ci.compare_values(&DataBox::Integer(1), &DataBox::Integer(2))
+        );
+        assert_eq!(
+            Some(Ordering::Less),
+            ci.compare_values(&DataBox::Null, &DataBox::from("x"))
+        );
+    }
+
+    #[test]
+    fn test_sort_key_agrees_with_compare() {
+        for collation in [
+            Collation::Binary,
+            Collation::CaseInsensitive,
+            Collation::Dictionary,
+        ] {
+            for a in WORDS {
+                for b in WORDS {
+                    assert_eq!(
+                        collation.compare(a, b),
+                        collation.sort_key(a).cmp(&collation.sort_key(b)),
+                        "{:?}: {:?} vs {:?}",
+                        collation,
+                        a,
+                        b
+                    );
+                }
+            }
+        }
+    }
+}
@@ -1,5 +1,5 @@
 use crate::common::error::DBError;
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use bytes::{Buf, Bytes};
 use std::any::Any;
 use std::borrow::Cow;
@@ -29,6 +29,40 @@ impl Display for DataType {
     }
 }
 
+impl DataType {
+    /// Returns `true` for the types taking part in numeric promotion.
+    pub fn is_numeric(&self) -> bool {
+        matches!(self, DataType::Integer | DataType::Long | DataType::Float)
+    }
+
+    /// Returns the common type two numeric types are promoted to for mixed-type comparisons and
+    /// arithmetic, or `None` if either of them is not numeric.
+    ///
+    /// | promote     | INTEGER | LONG  | FLOAT |
+    /// |-------------|---------|-------|-------|
+    /// | **INTEGER** | INTEGER | LONG  | FLOAT |
+    /// | **LONG**    | LONG    | LONG  | FLOAT |
+    /// | **FLOAT**   | FLOAT   | FLOAT | FLOAT |
+    pub fn promote(self, other: DataType) -> Option<DataType> {
+        match (self, other) {
+            (DataType::Float, t) | (t, DataType::Float) if t.is_numeric() => Some(DataType::Float),
+            (DataType::Long, t) | (t, DataType::Long) if t.is_numeric() => Some(DataType::Long),
+            (DataType::Integer, DataType::Integer) => Some(DataType::Integer),
+            _ => None,
+        }
+    }
+}
+
+/// What to do with a string or byte array longer than the max length of its column.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum LengthPolicy {
+    /// Reject the value with `DBError::LossyCastError`
+    #[default]
+    Reject,
+    /// Cut the value down to the max length, strings at a char boundary
+    Truncate,
+}
+
 #[derive(Clone, Debug, PartialEq, PartialOrd)]
 pub enum DataBox {
     Null,
@@ -68,7 +102,7 @@ impl Display for DataBox {
                 DataBox::Long(v) => v.to_string(),
                 DataBox::Float(v) => v.to_string(),
                 DataBox::String(v) => v.clone(),
-                DataBox::ByteArray(v) => String::from_utf8(v.clone()).unwrap(),
+                DataBox::ByteArray(v) => String::from_utf8_lossy(v).into_owned(),
             }
             .as_ref(),
         )
@@ -76,27 +110,46 @@ impl Display for DataBox {
 }
 
 impl DataBox {
+    /// Reads a value of the given type from the front of the buffer, where a string or byte array
+    /// takes its max length and a string is padded with trailing zero bytes.
+    ///
+    /// _Note_: Returns an error instead of panicking if the buffer is too short, and
+    ///         `DBError::Utf8Error` if a string is not valid UTF-8.
     pub fn from_bytes(mut buf: Bytes, datatype: DataType) -> Result<Self> {
+        let size = match datatype {
+            DataType::Boolean => 1,
+            DataType::Integer => 4,
+            DataType::Float | DataType::Long => 8,
+            DataType::String(len) | DataType::ByteArray(len) => len,
+        };
+        if buf.remaining() < size {
+            return Err(anyhow!(
+                "cannot read {} from {} bytes, expected {}",
+                datatype,
+                buf.remaining(),
+                size
+            ));
+        }
+
         match datatype {
             DataType::Boolean => Ok(DataBox::Boolean(buf.get_u8() == 1)),
             DataType::Integer => Ok(DataBox::Integer(buf.get_i32())),
             DataType::Float => Ok(DataBox::Float(buf.get_f64())),
             DataType::Long => Ok(DataBox::Long(buf.get_i64())),
             DataType::String(len) => {
-                let mut dst: Vec<u8> = Vec::with_capacity(len);
-                buf.copy_to_slice(dst.as_mut_slice());
-                Ok(DataBox::String(String::from_utf8(dst)?))
-            }
-            DataType::ByteArray(len) => {
-                let mut dst: Vec<u8> = Vec::with_capacity(len);
-                buf.copy_to_slice(dst.as_mut_slice());
-                Ok(DataBox::ByteArray(dst))
+                let bytes = buf.split_to(len);
+                let end = bytes.iter().rposition(|b| *b != 0).map_or(0, |i| i + 1);
+                match std::str::from_utf8(&bytes[..end]) {
+                    Ok(s) => Ok(DataBox::String(s.to_owned())),
+                    Err(e) => Err(DBError::Utf8Error(e.valid_up_to()).into()),
+                }
             }
+            DataType::ByteArray(len) => Ok(DataBox::ByteArray(buf.split_to(len).to_vec())),
         }
     }
 
-    pub fn from_string(mut s: String, datatype: DataType) -> Result<Self> {
-        todo!()
+    pub fn from_string(s: String, datatype: DataType) -> Result<Self> {
+        Ok(DataBox::String(s).cast(datatype)?)
     }
 
     pub fn from_object(any: &dyn Any) -> Result<Self> {
@@ -115,6 +168,129 @@ impl DataBox {
         }
     }
 
+    /// Converts the value into another type, or returns `DBError::CastError` if the conversion is
+    /// impossible and `DBError::LossyCastError` if the value cannot be represented exactly in that type.
+    ///
+    /// _Note_: NULL can be cast to any type, any value can be cast to a string, and a string is parsed
+    ///         when cast to a boolean or numeric type. The length of a string or byte array type is its
+    ///         max length.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let v = DataBox::Integer(7).cast(DataType::Float).unwrap(); // DataBox::Float(7.0)
+    /// let v = DataBox::from("42").cast(DataType::Long).unwrap(); // DataBox::Long(42)
+    /// let e = DataBox::Float(7.5).cast(DataType::Integer).unwrap_err(); // DBError::LossyCastError
+    /// ```
+    pub fn cast(self, datatype: DataType) -> Result<DataBox, DBError> {
+        self.cast_with(datatype, LengthPolicy::Reject)
+    }
+
+    /// Converts the value into another type like `cast()`, except that a string or byte array longer
+    /// than the max length of a string or byte array type is handled according to `policy`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let v = DataBox::from("héllo").cast_with(DataType::String(2), LengthPolicy::Truncate).unwrap(); // DataBox::String("h")
+    /// let e = DataBox::from("héllo").cast_with(DataType::String(2), LengthPolicy::Reject).unwrap_err(); // DBError::LossyCastError
+    /// ```
+    pub fn cast_with(self, datatype: DataType, policy: LengthPolicy) -> Result<DataBox, DBError> {
+        let lossy = |v| Err(DBError::LossyCastError(v, datatype));
+        let truncate = |v: &DataBox| {
+            policy == LengthPolicy::Truncate
+                && matches!(v, DataBox::String(_) | DataBox::ByteArray(_))
+        };
+        let invalid = |v| Err(DBError::CastError(v, datatype));
+
+        match (self, datatype) {
+            (DataBox::Null, _) => Ok(DataBox::Null),
+            (v @ DataBox::Boolean(_), DataType::Boolean)
+            | (v @ DataBox::Integer(_), DataType::Integer)
+            | (v @ DataBox::Long(_), DataType::Long)
+            | (v @ DataBox::Float(_), DataType::Float) => Ok(v),
+
+            // numeric conversions
+            (DataBox::Integer(v), DataType::Long) => Ok(DataBox::Long(v as i64)),
+            (DataBox::Integer(v), DataType::Float) => Ok(DataBox::Float(v as f64)),
+            (DataBox::Long(v), DataType::Integer) if i32::try_from(v).is_ok() => {
+                Ok(DataBox::Integer(v as i32))
+            }
+            (DataBox::Long(v), DataType::Float) if (v as f64) as i128 == v as i128 => {
+                Ok(DataBox::Float(v as f64))
+            }
+            (DataBox::Float(v), DataType::Integer)
+                if v.fract() == 0.0 && v >= i32::MIN as f64 && v <= i32::MAX as f64 =>
+            {
+                Ok(DataBox::Integer(v as i32))
+            }
+            // `i64::MAX as f64` rounds up to 2^63, which is already out of range
+            (DataBox::Float(v), DataType::Long)
+                if v.fract() == 0.0 && v >= i64::MIN as f64 && v < i64::MAX as f64 =>
+            {
+                Ok(DataBox::Long(v as i64))
+            }
+            (v @ (DataBox::Long(_) | DataBox::Float(_)), t) if t.is_numeric() => lossy(v),
+
+            // string and byte array conversions
+            (v, DataType::String(max_len)) => {
+                let s = match &v {
+                    DataBox::String(s) => s.clone(),
+                    DataBox::ByteArray(bytes) => match std::str::from_utf8(bytes) {
+                        Ok(s) => s.to_owned(),
+                        Err(_) => return invalid(v),
+                    },
+                    v => v.to_string(),
+                };
+                if s.len() <= max_len {
+                    Ok(DataBox::String(s))
+                } else if truncate(&v) {
+                    let mut end = max_len;
+                    while !s.is_char_boundary(end) {
+                        end -= 1;
+                    }
+                    Ok(DataBox::String(s[..end].to_owned()))
+                } else {
+                    lossy(v)
+                }
+            }
+            (v @ (DataBox::String(_) | DataBox::ByteArray(_)), DataType::ByteArray(max_len)) => {
+                let mut bytes = v.to_bytes();
+                if bytes.len() <= max_len {
+                    Ok(DataBox::ByteArray(bytes))
+                } else if truncate(&v) {
+                    bytes.truncate(max_len);
+                    Ok(DataBox::ByteArray(bytes))
+                } else {
+                    lossy(v)
+                }
+            }
+
+            // parse a string into boolean or numeric
+            (DataBox::String(s), t) => {
+                let v = s.trim();
+                let parsed = match t {
+                    DataType::Boolean if v.eq_ignore_ascii_case("true") => {
+                        Some(DataBox::Boolean(true))
+                    }
+                    DataType::Boolean if v.eq_ignore_ascii_case("false") => {
+                        Some(DataBox::Boolean(false))
+                    }
+                    DataType::Integer => v.parse().ok().map(DataBox::Integer),
+                    DataType::Long => v.parse().ok().map(DataBox::Long),
+                    DataType::Float => v.parse().ok().map(DataBox::Float),
+                    _ => None,
+                };
+                match parsed {
+                    Some(v) => Ok(v),
+                    None => invalid(DataBox::String(s)),
+                }
+            }
+
+            (v, _) => invalid(v),
+        }
+    }
+
     pub fn boolean(self) -> Result<bool, DBError> {
         match self {
             Self::Boolean(b) => Ok(b),
@@ -240,9 +416,370 @@ impl From<&[u8]> for DataBox {
     }
 }
 
+macro_rules! try_from_databox {
+    ($t:ty, $accessor:ident) => {
+        impl TryFrom<DataBox> for $t {
+            type Error = DBError;
+
+            fn try_from(v: DataBox) -> Result<Self, Self::Error> {
+                v.$accessor()
+            }
+        }
+    };
+}
+
+try_from_databox!(bool, boolean);
+try_from_databox!(i32, integer);
+try_from_databox!(i64, long);
+try_from_databox!(f64, float);
+try_from_databox!(String, string);
+try_from_databox!(Vec<u8>, byte_array);
+
+/// Converts a Rust value into the values of a record.
+///
+/// # Example
+///
+/// ```
+/// let values = (1, "rookie", 4.5).into_record(); // [Integer(1), String("rookie"), Float(4.5)]
+/// ```
+pub trait IntoRecord {
+    fn into_record(self) -> Vec<DataBox>;
+}
+
+/// Converts the values of a record back into a Rust value, or returns an error if the number
+/// or the types of the values do not match.
+///
+/// # Example
+///
+/// ```
+/// let (id, name) = <(i32, String)>::from_record(vec![DataBox::Integer(1), DataBox::from("rookie")]).unwrap();
+/// ```
+pub trait FromRecord: Sized {
+    fn from_record(values: Vec<DataBox>) -> Result<Self, DBError>;
+}
+
+impl IntoRecord for Vec<DataBox> {
+    fn into_record(self) -> Vec<DataBox> {
+        self
+    }
+}
+
+impl FromRecord for Vec<DataBox> {
+    fn from_record(values: Vec<DataBox>) -> Result<Self, DBError> {
+        Ok(values)
+    }
+}
+
+macro_rules! record_tuple {
+    ($len:expr; $($t:ident $i:tt),+) => {
+        impl<$($t: Into<DataBox>),+> IntoRecord for ($($t,)+) {
+            fn into_record(self) -> Vec<DataBox> {
+                vec![$(self.$i.into()),+]
+            }
+        }
+
+        impl<$($t: TryFrom<DataBox, Error = DBError>),+> FromRecord for ($($t,)+) {
+            fn from_record(values: Vec<DataBox>) -> Result<Self, DBError> {
+                if values.len() != $len {
+                    return Err(DBError::RecordSizeError($len, values.len()));
+                }
+
+                let mut values = values.into_iter();
+                Ok(($($t::try_from(values.next().unwrap())?,)+))
+            }
+        }
+    };
+}
+
+record_tuple!(1; A 0);
+record_tuple!(2; A 0, B 1);
+record_tuple!(3; A 0, B 1, C 2);
+record_tuple!(4; A 0, B 1, C 2, D 3);
+record_tuple!(5; A 0, B 1, C 2, D 3, E 4);
+record_tuple!(6; A 0, B 1, C 2, D 3, E 4, F 5);
+record_tuple!(7; A 0, B 1, C 2, D 3, E 4, F 5, G 6);
+record_tuple!(8; A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7);
+
 #[cfg(test)]
 mod tests {
+    use super::*;
 
     #[test]
     fn test_bool_type() {}
+
+    #[test]
+    fn test_try_from_databox() -> Result<(), DBError> {
+        assert!(bool::try_from(DataBox::Boolean(true))?);
+        assert_eq!(7, i32::try_from(DataBox::Integer(7))?);
+        assert_eq!(7, i64::try_from(DataBox::Long(7))?);
+        assert_eq!(0.5, f64::try_from(DataBox::Float(0.5))?);
+        assert_eq!("rookie", String::try_from(DataBox::from("rookie"))?);
+        assert_eq!(
+            vec![0x1_u8],
+            Vec::<u8>::try_from(DataBox::from(vec![0x1_u8]))?
+        );
+
+        assert_eq!(
+            Err(DBError::TypeError(DataBox::Long(7), "integer")),
+            i32::try_from(DataBox::Long(7))
+        );
+        assert_eq!(
+            Err(DBError::TypeError(DataBox::Null, "string")),
+            String::try_from(DataBox::Null)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_promote() {
+        use DataType::*;
+
+        assert_eq!(Some(Integer), Integer.promote(Integer));
+        assert_eq!(Some(Long), Integer.promote(Long));
+        assert_eq!(Some(Long), Long.promote(Integer));
+        assert_eq!(Some(Float), Integer.promote(Float));
+        assert_eq!(Some(Float), Float.promote(Long));
+        assert_eq!(Some(Float), Float.promote(Float));
+        assert_eq!(None, Integer.promote(Boolean));
+        assert_eq!(None, Float.promote(String(8)));
+        assert_eq!(None, Boolean.promote(Boolean));
+    }
+
+    #[test]
+    fn test_cast_numeric() -> Result<(), DBError> {
+        assert_eq!(
+            DataBox::Long(-7),
+            DataBox::Integer(-7).cast(DataType::Long)?
+        );
+        assert_eq!(
+            DataBox::Float(-7.0),
+            DataBox::Integer(-7).cast(DataType::Float)?
+        );
+        assert_eq!(
+            DataBox::Integer(7),
+            DataBox::Long(7).cast(DataType::Integer)?
+        );
+        assert_eq!(DataBox::Float(7.0), DataBox::Long(7).cast(DataType::Float)?);
+        assert_eq!(
+            DataBox::Integer(-7),
+            DataBox::Float(-7.0).cast(DataType::Integer)?
+        );
+        assert_eq!(
+            DataBox::Long(1 << 40),
+            DataBox::Float(2_f64.powi(40)).cast(DataType::Long)?
+        );
+        assert_eq!(DataBox::Null, DataBox::Null.cast(DataType::Integer)?);
+
+        let lossy =
+            |v: DataBox, t| assert_eq!(Err(DBError::LossyCastError(v.clone(), t)), v.cast(t));
+        lossy(DataBox::Long(i64::MAX), DataType::Integer);
+        lossy(DataBox::Long(i64::MAX), DataType::Float);
+        lossy(DataBox::Long((1 << 53) + 1), DataType::Float);
+        lossy(DataBox::Float(7.5), DataType::Integer);
+        lossy(DataBox::Float(1e10), DataType::Integer);
+        lossy(DataBox::Float(2_f64.powi(63)), DataType::Long);
+        assert!(matches!(
+            DataBox::Float(f64::NAN).cast(DataType::Long),
+            Err(DBError::LossyCastError(DataBox::Float(_), DataType::Long))
+        ));
+        lossy(DataBox::Float(f64::INFINITY), DataType::Integer);
+
+        // mixed-type comparison after promotion
+        let t = DataType::Integer.promote(DataType::Float).unwrap();
+        assert!(DataBox::Integer(2).cast(t)? < DataBox::Float(2.5).cast(t)?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_cast_string() -> Result<(), DBError> {
+        assert_eq!(
+            DataBox::from("42"),
+            DataBox::Integer(42).cast(DataType::String(8))?
+        );
+        assert_eq!(
+            DataBox::from("TRUE"),
+            DataBox::Boolean(true).cast(DataType::String(8))?
+        );
+        assert_eq!(
+            DataBox::from("ab"),
+            DataBox::from(vec![b'a', b'b']).cast(DataType::String(2))?
+        );
+        assert_eq!(
+            DataBox::from(vec![b'a', b'b']),
+            DataBox::from("ab").cast(DataType::ByteArray(2))?
+        );
+
+        assert_eq!(
+            DataBox::Boolean(true),
+            DataBox::from(" True ").cast(DataType::Boolean)?
+        );
+        assert_eq!(
+            DataBox::Boolean(false),
+            DataBox::from("FALSE").cast(DataType::Boolean)?
+        );
+        assert_eq!(
+            DataBox::Integer(-3),
+            DataBox::from(" -3").cast(DataType::Integer)?
+        );
+        assert_eq!(
+            DataBox::Long(1 << 40),
+            DataBox::from("1099511627776").cast(DataType::Long)?
+        );
+        assert_eq!(
+            DataBox::Float(0.25),
+            DataBox::from("0.25").cast(DataType::Float)?
+        );
+        assert_eq!(
+            DataBox::Integer(12),
+            DataBox::from_string("12".to_string(), DataType::Integer).unwrap()
+        );
+
+        assert_eq!(
+            Err(DBError::LossyCastError(
+                DataBox::Integer(12345),
+                DataType::String(3)
+            )),
+            DataBox::Integer(12345).cast(DataType::String(3))
+        );
+        assert_eq!(
+            Err(DBError::LossyCastError(
+                DataBox::from("abc"),
+                DataType::ByteArray(2)
+            )),
+            DataBox::from("abc").cast(DataType::ByteArray(2))
+        );
+
+        let invalid = |v: DataBox, t| assert_eq!(Err(DBError::CastError(v.clone(), t)), v.cast(t));
+        invalid(DataBox::from("yes"), DataType::Boolean);
+        invalid(DataBox::from("1.5"), DataType::Integer);
+        invalid(DataBox::from("3000000000"), DataType::Integer);
+        invalid(DataBox::from(vec![0xC3_u8]), DataType::String(8));
+        invalid(DataBox::from(vec![0x1_u8]), DataType::Integer);
+        invalid(DataBox::Integer(1), DataType::Boolean);
+        invalid(DataBox::Boolean(true), DataType::Integer);
+        invalid(DataBox::Integer(1), DataType::ByteArray(8));
+        assert!(DataBox::from_string("x".to_string(), DataType::Float).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_cast_with_truncate() -> Result<(), DBError> {
+        let truncate = |v: DataBox, t| v.cast_with(t, LengthPolicy::Truncate);
+
+        assert_eq!(
+            DataBox::from("ro"),
+            truncate(DataBox::from("rookie"), DataType::String(2))?
+        );
+        // "é" takes 2 bytes, and is dropped rather than split
+        assert_eq!(
+            DataBox::from("h"),
+            truncate(DataBox::from("héllo"), DataType::String(2))?
+        );
+        assert_eq!(
+            DataBox::from("hé"),
+            truncate(DataBox::from("héllo"), DataType::String(3))?
+        );
+        assert_eq!(
+            DataBox::from(""),
+            truncate(DataBox::from("数据"), DataType::String(2))?
+        );
+        assert_eq!(
+            DataBox::from("ab"),
+            truncate(DataBox::from(vec![b'a', b'b', b'c']), DataType::String(2))?
+        );
+        assert_eq!(
+            DataBox::from(vec![0xE6_u8]),
+            truncate(DataBox::from("数据"), DataType::ByteArray(1))?
+        );
+        assert_eq!(
+            DataBox::from("ok"),
+            truncate(DataBox::from("ok"), DataType::String(8))?
+        );
+
+        // only strings and byte arrays are truncated, and invalid UTF-8 is still rejected
+        assert!(truncate(DataBox::Integer(12345), DataType::String(3)).is_err());
+        assert!(truncate(DataBox::from(vec![b'a', 0xC3]), DataType::String(1)).is_err());
+        assert_eq!(
+            Err(DBError::LossyCastError(
+                DataBox::from("héllo"),
+                DataType::String(2)
+            )),
+            DataBox::from("héllo").cast_with(DataType::String(2), LengthPolicy::Reject)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_from_bytes() -> Result<()> {
+        let from_bytes = |v: &[u8], t| DataBox::from_bytes(Bytes::copy_from_slice(v), t);
+
+        assert_eq!(
+            DataBox::Integer(-2),
+            from_bytes(&(-2_i32).to_be_bytes(), DataType::Integer)?
+        );
+        assert_eq!(
+            DataBox::from("héllo"),
+            from_bytes(b"h\xC3\xA9llo\0\0", DataType::String(8))?
+        );
+        assert_eq!(DataBox::from(""), from_bytes(&[0; 4], DataType::String(4))?);
+        assert_eq!(
+            DataBox::from(vec![1_u8, 0]),
+            from_bytes(&[1, 0, 7], DataType::ByteArray(2))?
+        );
+
+        let err = from_bytes(b"h\xC3llo", DataType::String(5)).unwrap_err();
+        assert_eq!(Some(&DBError::Utf8Error(1)), err.downcast_ref());
+        assert!(from_bytes(b"abc", DataType::String(4)).is_err());
+        assert!(from_bytes(&[0; 3], DataType::Integer).is_err());
+        assert!(from_bytes(&[], DataType::Boolean).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!("héllo", DataBox::from("héllo").to_string());
+        assert_eq!("ab", DataBox::from(vec![b'a', b'b']).to_string());
+        assert_eq!("a\u{FFFD}", DataBox::from(vec![b'a', 0xC3]).to_string());
+    }
+
+    #[test]
+    fn test_into_record() {
+        assert_eq!(vec![DataBox::Integer(1)], (1,).into_record());
+        assert_eq!(
+            vec![
+                DataBox::Integer(1),
+                DataBox::from("rookie"),
+                DataBox::Float(4.5),
+                DataBox::Boolean(false),
+                DataBox::Long(2),
+            ],
+            (1, "rookie", 4.5, false, 2_i64).into_record()
+        );
+    }
+
+    #[test]
+    fn test_from_record() -> Result<(), DBError> {
+        let values = vec![DataBox::Integer(1), DataBox::from("rookie")];
+        assert_eq!(
+            (1, "rookie".to_string()),
+            <(i32, String)>::from_record(values.clone())?
+        );
+        assert_eq!(values.clone(), Vec::<DataBox>::from_record(values.clone())?);
+
+        assert_eq!(
+            Err(DBError::RecordSizeError(3, 2)),
+            <(i32, String, bool)>::from_record(values.clone())
+        );
+        assert_eq!(
+            Err(DBError::TypeError(DataBox::from("rookie"), "long")),
+            <(i32, i64)>::from_record(values)
+        );
+
+        Ok(())
+    }
 }
@@ -0,0 +1,287 @@
+use crate::databox::{Collation, DataBox};
+use anyhow::{anyhow, Result};
+
+// type tags in the same order as the `DataBox` variants, so that NULL sorts first
+const NULL_TAG: u8 = 0;
+const BOOLEAN_TAG: u8 = 1;
+const INTEGER_TAG: u8 = 2;
+const LONG_TAG: u8 = 3;
+const FLOAT_TAG: u8 = 4;
+const STRING_TAG: u8 = 5;
+const BYTE_ARRAY_TAG: u8 = 6;
+
+// a zero byte inside a string or byte array is escaped as `[0x00, 0xFF]`,
+// and the value is terminated by `[0x00, 0x00]`
+const ESCAPE: u8 = 0x00;
+const ESCAPED_ZERO: u8 = 0xFF;
+const TERMINATOR: u8 = 0x00;
+
+/// Order-preserving encoding of composite keys, so that comparing two encoded keys with a plain
+/// byte-wise comparison gives the same result as comparing the tuples of `DataBox` they came from.
+///
+/// Every value is written as a type tag followed by its payload:
+/// - NULL has no payload, and its tag is the smallest so NULL sorts first
+/// - integers and longs are big-endian with the sign bit flipped
+/// - floats are big-endian with the sign bit flipped if positive, and every bit flipped if negative
+/// - strings and byte arrays have their zero bytes escaped and are terminated by two zero bytes,
+///   so that a value sorts before any longer value it is a prefix of
+pub struct KeyEncoder;
+
+impl KeyEncoder {
+    /// Encodes a tuple of `DataBox` into an order-preserving byte string.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let a = KeyEncoder::encode(&[DataBox::Integer(-1), DataBox::from("b")]);
+    /// let b = KeyEncoder::encode(&[DataBox::Integer(1), DataBox::from("a")]);
+    /// assert!(a < b);
+    /// ```
+    pub fn encode(key: &[DataBox]) -> Vec<u8> {
+        let mut buf = vec![];
+        key.iter()
+            .for_each(|v| KeyEncoder::encode_value(v, &mut buf));
+        buf
+    }
+
+    /// Encodes a tuple of `DataBox` like `encode()`, except that strings are ordered under the collation
+    /// of their position in `collations`, or under `Collation::Binary` past its end.
+    ///
+    /// _Note_: A string under any other collation than binary is encoded as its sort key, so the key
+    ///         cannot be decoded back.
+    pub fn encode_collated(key: &[DataBox], collations: &[Collation]) -> Vec<u8> {
+        let mut buf = vec![];
+        for (i, v) in key.iter().enumerate() {
+            match (v, collations.get(i)) {
+                (DataBox::String(v), Some(collation)) if *collation != Collation::Binary => {
+                    buf.push(STRING_TAG);
+                    KeyEncoder::escape(&collation.sort_key(v), &mut buf);
+                }
+                (v, _) => KeyEncoder::encode_value(v, &mut buf),
+            }
+        }
+        buf
+    }
+
+    /// Decodes a byte string produced by `encode()` back into its tuple of `DataBox`.
+    pub fn decode(mut bytes: &[u8]) -> Result<Vec<DataBox>> {
+        let mut key = vec![];
+        while let Some((tag, rest)) = bytes.split_first() {
+            bytes = rest;
+            key.push(match *tag {
+                NULL_TAG => DataBox::Null,
+                BOOLEAN_TAG => DataBox::Boolean(KeyEncoder::take::<1>(&mut bytes)?[0] == 1),
+                INTEGER_TAG => {
+                    let v = u32::from_be_bytes(KeyEncoder::take(&mut bytes)?);
+                    DataBox::Integer((v ^ (1 << 31)) as i32)
+                }
+                LONG_TAG => {
+                    let v = u64::from_be_bytes(KeyEncoder::take(&mut bytes)?);
+                    DataBox::Long((v ^ (1 << 63)) as i64)
+                }
+                FLOAT_TAG => {
+                    let v = u64::from_be_bytes(KeyEncoder::take(&mut bytes)?);
+                    let v = if v >> 63 == 1 { v ^ (1 << 63) } else { !v };
+                    DataBox::Float(f64::from_bits(v))
+                }
+                STRING_TAG => {
+                    DataBox::String(String::from_utf8(KeyEncoder::unescape(&mut bytes)?)?)
+                }
+                BYTE_ARRAY_TAG => DataBox::ByteArray(KeyEncoder::unescape(&mut bytes)?),
+                tag => return Err(anyhow!("invalid key encoding: unknown type tag {}", tag)),
+            });
+        }
+        Ok(key)
+    }
+
+    fn encode_value(v: &DataBox, buf: &mut Vec<u8>) {
+        match v {
+            DataBox::Null => buf.push(NULL_TAG),
+            DataBox::Boolean(v) => {
+                buf.push(BOOLEAN_TAG);
+                buf.push(*v as u8);
+            }
+            DataBox::Integer(v) => {
+                buf.push(INTEGER_TAG);
+                buf.extend_from_slice(&(*v as u32 ^ (1 << 31)).to_be_bytes());
+            }
+            DataBox::Long(v) => {
+                buf.push(LONG_TAG);
+                buf.extend_from_slice(&(*v as u64 ^ (1 << 63)).to_be_bytes());
+            }
+            DataBox::Float(v) => {
+                // -0.0 == 0.0, so both must encode to the same bytes
+                let v = if *v == 0.0 { 0.0_f64 } else { *v }.to_bits();
+                let v = if v >> 63 == 1 { !v } else { v ^ (1 << 63) };
+                buf.push(FLOAT_TAG);
+                buf.extend_from_slice(&v.to_be_bytes());
+            }
+            DataBox::String(v) => {
+                buf.push(STRING_TAG);
+                KeyEncoder::escape(v.as_bytes(), buf);
+            }
+            DataBox::ByteArray(v) => {
+                buf.push(BYTE_ARRAY_TAG);
+                KeyEncoder::escape(v, buf);
+            }
+        }
+    }
+
+    fn escape(v: &[u8], buf: &mut Vec<u8>) {
+        for b in v {
+            if *b == ESCAPE {
+                buf.extend_from_slice(&[ESCAPE, ESCAPED_ZERO]);
+            } else {
+                buf.push(*b);
+            }
+        }
+        buf.extend_from_slice(&[ESCAPE, TERMINATOR]);
+    }
+
+    fn unescape(bytes: &mut &[u8]) -> Result<Vec<u8>> {
+        let mut v = vec![];
+        loop {
+            let cur: &[u8] = bytes;
+            match cur {
+                [ESCAPE, TERMINATOR, rest @ ..] => {
+                    *bytes = rest;
+                    return Ok(v);
+                }
+                [ESCAPE, ESCAPED_ZERO, rest @ ..] => {
+                    v.push(0);
+                    *bytes = rest;
+                }
+                [ESCAPE, ..] | [] => {
+                    return Err(anyhow!("invalid key encoding: unterminated value"))
+                }
+                [b, rest @ ..] => {
+                    v.push(*b);
+                    *bytes = rest;
+                }
+            }
+        }
+    }
+
+    fn take<const N: usize>(bytes: &mut &[u8]) -> Result<[u8; N]> {
+        if bytes.len() < N {
+            return Err(anyhow!("invalid key encoding: truncated value"));
+        }
+        let (v, rest) = bytes.split_at(N);
+        *bytes = rest;
+        Ok(v.try_into()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_preserves_order() {
+        let keys: Vec<Vec<DataBox>> = vec![
+            vec![DataBox::Null],
+            vec![DataBox::Boolean(false)],
+            vec![DataBox::Boolean(true)],
+            vec![DataBox::Integer(i32::MIN)],
+            vec![DataBox::Integer(-1)],
+            vec![DataBox::Integer(0)],
+            vec![DataBox::Integer(1), DataBox::Null],
+            vec![DataBox::Integer(1), DataBox::from("")],
+            vec![DataBox::Integer(1), DataBox::from("a")],
+            vec![DataBox::Integer(1), DataBox::from("a\0")],
+            vec![DataBox::Integer(1), DataBox::from("a\0\0")],
+            vec![DataBox::Integer(1), DataBox::from("a\x01")],
+            vec![DataBox::Integer(1), DataBox::from("ab")],
+            vec![DataBox::Integer(1), DataBox::from("b")],
+            vec![DataBox::Integer(i32::MAX)],
+            vec![DataBox::Long(i64::MIN)],
+            vec![DataBox::Long(-300)],
+            vec![DataBox::Long(300)],
+            vec![DataBox::Float(f64::NEG_INFINITY)],
+            vec![DataBox::Float(-2.5)],
+            vec![DataBox::Float(-0.001)],
+            vec![DataBox::Float(0.0)],
+            vec![DataBox::Float(0.001)],
+            vec![DataBox::Float(2.5)],
+            vec![DataBox::Float(f64::INFINITY)],
+            vec![DataBox::from(vec![0_u8])],
+            vec![DataBox::from(vec![0_u8, 0])],
+            vec![DataBox::from(vec![0xFF_u8])],
+        ];
+
+        for pair in keys.windows(2) {
+            assert!(pair[0] < pair[1], "{:?} < {:?}", pair[0], pair[1]);
+            assert!(
+                KeyEncoder::encode(&pair[0]) < KeyEncoder::encode(&pair[1]),
+                "encode({:?}) < encode({:?})",
+                pair[0],
+                pair[1]
+            );
+        }
+    }
+
+    #[test]
+    fn test_encode_collated() {
+        let collations = [Collation::Binary, Collation::CaseInsensitive];
+        let encode = |a: &str, b: &str| {
+            KeyEncoder::encode_collated(&[DataBox::from(a), DataBox::from(b)], &collations)
+        };
+
+        assert!(encode("B", "z") < encode("a", "a"));
+        assert!(encode("a", "B") > encode("a", "a"));
+        assert_eq!(encode("a", "ABC"), encode("a", "abc"));
+        assert!(encode("a", "ab") < encode("a", "AB\0"));
+
+        let key = [DataBox::from("B"), DataBox::Integer(1), DataBox::from("C")];
+        assert_eq!(
+            KeyEncoder::encode(&key),
+            KeyEncoder::encode_collated(&key, &[])
+        );
+        let collated = KeyEncoder::encode_collated(&key, &[Collation::Dictionary; 2]);
+        assert_ne!(KeyEncoder::encode(&key), collated);
+        assert!(collated.ends_with(&KeyEncoder::encode(&key[1..])));
+    }
+
+    #[test]
+    fn test_encode_equal_values() {
+        assert_eq!(DataBox::Float(-0.0), DataBox::Float(0.0));
+        assert_eq!(
+            KeyEncoder::encode(&[DataBox::Float(-0.0)]),
+            KeyEncoder::encode(&[DataBox::Float(0.0)])
+        );
+        assert!(
+            KeyEncoder::encode(&[DataBox::Float(-0.0)])
+                > KeyEncoder::encode(&[DataBox::Float(-f64::MIN_POSITIVE)])
+        );
+    }
+
+    #[test]
+    fn test_decode() -> Result<()> {
+        let key = vec![
+            DataBox::Null,
+            DataBox::Boolean(true),
+            DataBox::Integer(-42),
+            DataBox::Long(1 << 40),
+            DataBox::Float(-1.5),
+            DataBox::from("a\0b"),
+            DataBox::from(vec![0_u8, 0xFF, 0]),
+            DataBox::from(""),
+        ];
+        assert_eq!(key, KeyEncoder::decode(&KeyEncoder::encode(&key))?);
+        assert!(KeyEncoder::decode(&[]).unwrap().is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_decode_invalid() {
+        // unknown type tag
+        assert!(KeyEncoder::decode(&[7]).is_err());
+        // truncated integer
+        assert!(KeyEncoder::decode(&[INTEGER_TAG, 0x80, 0x00]).is_err());
+        // unterminated string
+        assert!(KeyEncoder::decode(&[STRING_TAG, b'a', 0x00]).is_err());
+        // invalid utf-8 string
+        assert!(KeyEncoder::decode(&[STRING_TAG, 0xC3, 0x00, 0x00]).is_err());
+    }
+}
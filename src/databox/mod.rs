@@ -1,3 +1,7 @@
+mod collation;
 mod data_types;
+mod key_encoder;
 
+pub use collation::*;
 pub use data_types::*;
+pub use key_encoder::*;
@@ -1,4 +1,5 @@
 use crate::common::constant::{DATA_PAGES_PER_HEADER, MAX_HEADER_PAGE, PAGE_SIZE};
+use crate::common::error::DBError;
 use crate::common::Bit;
 use crate::recovery::RecoveryManager;
 use anyhow::{anyhow, Result};
@@ -21,6 +22,14 @@ pub struct PartitionHandle {
     /// Recovery manager
     /// TODO: type is missing
     recovery_manager: Box<dyn RecoveryManager>,
+    /// Whether the OS file was opened read-only
+    read_only: bool,
+    /// Index of the first header page that may have free data pages, every header page before it is full
+    free_header_hint: usize,
+    /// Maximum number of allocated data pages, `None` for no limit
+    page_quota: Option<usize>,
+    /// Number of allocated data pages, the sum of the master page entries
+    allocated: usize,
 }
 
 impl Drop for PartitionHandle {
@@ -34,10 +43,14 @@ impl PartitionHandle {
         Self {
             file: None,
             part_lock: Mutex::new(0),
-            master_page: Vec::with_capacity(MAX_HEADER_PAGE),
+            master_page: vec![0; MAX_HEADER_PAGE],
             header_pages: Vec::with_capacity(MAX_HEADER_PAGE),
             part_num,
             recovery_manager,
+            read_only: false,
+            free_header_hint: 0,
+            page_quota: None,
+            allocated: 0,
         }
     }
 
@@ -48,35 +61,97 @@ impl PartitionHandle {
                 .read(true)
                 .write(true)
                 .create(true)
+                .truncate(false)
                 .open(file_name)?,
         );
+        self.read_only = false;
+
+        self.load()
+    }
+
+    /// Opens the OS file read-only and loads the master page and header pages.
+    ///
+    /// _Note_: The file must already exist. Any attempt to allocate, free or write pages
+    /// returns `DBError::ReadOnlyError`, so several processes can safely open the same file.
+    pub fn open_read_only(&mut self, file_name: String) -> Result<()> {
+        self.file = Some(OpenOptions::new().read(true).open(file_name)?);
+        self.read_only = true;
+
+        self.load()
+    }
+
+    /// Returns `true` if the partition was opened with `open_read_only()`.
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    /// Limits the number of data pages that can be allocated in the partition, or removes the limit with `None`.
+    ///
+    /// _Note_: Pages allocated beyond a lower quota are kept, but no page can be allocated until enough are freed.
+    pub fn set_page_quota(&mut self, page_quota: Option<usize>) {
+        self.page_quota = page_quota;
+    }
+
+    /// Returns the number of allocated data pages, as recorded in the master page.
+    pub fn allocated_pages(&self) -> usize {
+        self.allocated
+    }
+
+    /// Sets the number of allocated data pages under a header page, and moves the allocated page count
+    /// by the change of the entry, so that the count stays the sum of the master page entries even if
+    /// the entry disagreed with its bitmap on disk.
+    fn set_master_page_entry(&mut self, header_index: usize, count: u16) {
+        self.allocated = self.allocated - self.master_page[header_index] as usize + count as usize;
+        self.master_page[header_index] = count;
+    }
+
+    /// Returns `DBError::QuotaExceededError` if allocating `n` more data pages would exceed the quota.
+    fn check_quota(&self, n: usize) -> Result<()> {
+        match self.page_quota {
+            Some(quota) if self.allocated + n > quota => {
+                Err(DBError::QuotaExceededError(self.part_num, quota).into())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Loads the master page and header pages from the OS file.
+    fn load(&mut self) -> Result<()> {
+        // forget the file this handle was opened on before, if any
+        self.master_page.fill(0);
+        self.header_pages.clear();
+        self.free_header_hint = 0;
+        self.allocated = 0;
 
         // https://stackoverflow.com/questions/69738600/simplest-way-to-unwrap-an-option-and-return-error-if-none-anyhow
 
         match self.file {
-            None => return Err(anyhow!("Could not open or read file")),
+            None => Err(anyhow!("Could not open or read file")),
             Some(ref file) => {
                 let length = file.metadata()?.len();
                 if length == 0 {
                     // new file, write empty master page
+                    self.check_writable()?;
                     self.write_master_page()
                 } else {
                     // old file, read in master page + header pages
-                    let mut buf = BytesMut::with_capacity(PAGE_SIZE);
-                    file.read_at(buf.as_mut(), Self::master_page_offset() as u64)?;
+                    let mut master_page = vec![0_u8; PAGE_SIZE];
+                    file.read_exact_at(&mut master_page, Self::master_page_offset() as u64)?;
 
+                    let mut buf = master_page.as_slice();
                     for i in 0..MAX_HEADER_PAGE {
-                        self.master_page.insert(i, buf.get_u16());
+                        self.master_page[i] = buf.get_u16();
                         if Self::header_page_offset(i) < length as usize {
                             // load header page that were already in the file
-                            let mut header_page: Vec<u8> = Vec::with_capacity(PAGE_SIZE);
-                            file.read_at(
-                                header_page.as_mut_slice(),
+                            let mut header_page = vec![0_u8; PAGE_SIZE];
+                            file.read_exact_at(
+                                &mut header_page,
                                 Self::header_page_offset(i) as u64,
-                            );
-                            self.header_pages.insert(i, header_page);
+                            )?;
+                            self.header_pages.push(header_page);
                         }
                     }
+                    self.allocated = self.master_page.iter().map(|v| *v as usize).sum();
 
                     Ok(())
                 }
@@ -84,61 +159,110 @@ impl PartitionHandle {
         }
     }
 
+    /// Returns an error if the partition was opened read-only.
+    fn check_writable(&self) -> Result<()> {
+        if self.read_only {
+            Err(DBError::ReadOnlyError(self.part_num).into())
+        } else {
+            Ok(())
+        }
+    }
+
     /// Allocates a new page in the partition, and return the allocated DataPage number.
     pub fn alloc_page(&mut self) -> Result<usize> {
-        let mut header_index = -1_isize;
-        let mut page_index = -1_isize;
+        self.check_writable()?;
 
-        // get free header page
-        for i in 0..MAX_HEADER_PAGE {
-            if let Some(header_page) = self.master_page.get(i) {
-                if *header_page < DATA_PAGES_PER_HEADER as u16 {
-                    header_index = i as isize;
-                    break;
-                }
+        // get free header page, skipping the header pages known to be full
+        let header_index = match self.master_page[self.free_header_hint..]
+            .iter()
+            .position(|v| (*v as usize) < DATA_PAGES_PER_HEADER)
+        {
+            None => {
+                self.free_header_hint = MAX_HEADER_PAGE;
+                return Err(anyhow!("no free pages - partition has reached max size"));
             }
-        }
+            Some(i) => self.free_header_hint + i,
+        };
+        self.free_header_hint = header_index;
 
-        if header_index == -1 {
-            return Err(anyhow!("no free pages - partition has reached max size"));
+        // get free data page
+        let page_index = match self.header_pages.get(header_index) {
+            None => 0,
+            Some(header_content) => Bit::first_zero_bit(header_content.as_slice())
+                .ok_or_else(|| anyhow!("header page should have free space, but doesn't"))?,
+        };
+
+        self.alloc_page_specific(header_index, page_index as usize)
+    }
+
+    /// Allocates `len` contiguous DataPages in the partition, and return the first allocated DataPage number.
+    ///
+    /// _Note_: The data pages of an extent are all managed by the same header page, so that they are also
+    /// contiguous in the OS file and can be read with sequential IO. Each page is freed with `free_page()`.
+    pub fn alloc_extent(&mut self, len: usize) -> Result<usize> {
+        self.check_writable()?;
+
+        if len == 0 || len > DATA_PAGES_PER_HEADER {
+            return Err(DBError::IllegalArgumentError("extent length out of range").into());
         }
+        self.check_quota(len)?;
 
-        // get free data page
-        match self.header_pages.get(header_index as usize) {
-            None => {
-                page_index = 0;
+        // get the first header page with a free run of `len` data pages
+        let mut extent = None;
+        for header_index in self.free_header_hint..MAX_HEADER_PAGE {
+            if self.master_page[header_index] as usize + len > DATA_PAGES_PER_HEADER {
+                continue;
             }
-            Some(header_content) => {
-                for i in 0..DATA_PAGES_PER_HEADER {
-                    if Bit::get_bit(header_content.as_slice(), i as u32)?.eq(&Bit::Zero) {
-                        page_index = i as isize;
-                        break;
-                    }
-                }
-
-                if page_index == -1 {
-                    return Err(anyhow!("header page should have free space, but doesn't"));
-                }
+            let page_index = match self.header_pages.get(header_index) {
+                None => Some(0),
+                Some(header_content) => Bit::first_zero_run(header_content, len as u32),
+            };
+            if let Some(page_index) = page_index {
+                extent = Some((header_index, page_index as usize));
+                break;
             }
         }
 
-        self.alloc_page_specific(header_index as usize, page_index as usize)
+        let (header_index, page_index) = match extent {
+            None => return Err(anyhow!("no free extent of {} pages in partition", len)),
+            Some(extent) => extent,
+        };
+
+        if header_index >= self.header_pages.len() {
+            self.header_pages
+                .resize_with(header_index + 1, || vec![0; PAGE_SIZE]);
+        }
+        let header_content = &mut self.header_pages[header_index];
+        Bit::set_range(header_content, page_index as u32, (page_index + len) as u32)?;
+        let count = Bit::count_ones(header_content) as u16;
+        self.set_master_page_entry(header_index, count);
+
+        // TODO transaction and recovery manager, log the allocation of each page like `alloc_page_specific()`
+
+        self.write_master_page()?;
+        self.write_header_page(header_index)?;
+
+        Ok(page_index + header_index * DATA_PAGES_PER_HEADER)
     }
 
     /// Allocates a new page in the partition, and return the allocated DataPage number.
     pub fn alloc_page_specific(&mut self, header_index: usize, page_index: usize) -> Result<usize> {
-        let header_content: &mut Vec<u8> = match self.header_pages.get_mut(header_index) {
-            None => {
-                let header_content: Vec<u8> = Vec::with_capacity(PAGE_SIZE);
-                self.header_pages.insert(header_index, header_content);
+        self.check_writable()?;
+        if header_index >= MAX_HEADER_PAGE {
+            return Err(DBError::IllegalArgumentError("header index out of range").into());
+        }
+        if page_index >= DATA_PAGES_PER_HEADER {
+            return Err(DBError::IllegalArgumentError("page index out of range").into());
+        }
+        self.check_quota(1)?;
 
-                // here cannot return `&mut header_content` directly, because the ownership of `header_content`
-                // was moved into header_pages after `insert` operation.
-                // for the moment, just get the reference from header_pages which stored in the header_index slot.
-                unsafe { self.header_pages.get_unchecked_mut(header_index) }
-            }
-            Some(header_content) => header_content,
-        };
+        // header pages are kept as a prefix of the file, so every header page before
+        // `header_index` that has not been loaded yet is filled in as an empty bitmap.
+        if header_index >= self.header_pages.len() {
+            self.header_pages
+                .resize_with(header_index + 1, || vec![0; PAGE_SIZE]);
+        }
+        let header_content = &mut self.header_pages[header_index];
 
         if Bit::get_bit(header_content, page_index as u32)?.eq(&Bit::One) {
             Err(anyhow!(
@@ -149,8 +273,8 @@ impl PartitionHandle {
             ))
         } else {
             Bit::set_bit(header_content.as_mut_slice(), page_index as u32, Bit::One)?;
-            self.master_page
-                .insert(header_index, Bit::count_ones(header_content) as u16);
+            let count = Bit::count_ones(header_content) as u16;
+            self.set_master_page_entry(header_index, count);
 
             let page_num = page_index + header_index * DATA_PAGES_PER_HEADER;
 
@@ -163,7 +287,7 @@ impl PartitionHandle {
             // recoveryManager.diskIOHook(vpn);
 
             // flush the master page and header pages to Disk
-            self.write_master_page();
+            self.write_master_page()?;
             self.write_header_page(header_index)?;
 
             Ok(page_num)
@@ -187,6 +311,8 @@ impl PartitionHandle {
 
     /// Writes to a DataPage. Assumes that the partition lock is held.
     pub fn write_page(&self, page_num: usize, buf: &[u8]) -> Result<()> {
+        self.check_writable()?;
+
         if self.is_not_allocated_page(page_num)? {
             Err(anyhow!("page {} is not allocated", page_num))
         } else {
@@ -235,6 +361,8 @@ impl PartitionHandle {
 
     /// Frees a DataPage in the partition from used.
     pub fn free_page(&mut self, page_num: usize) -> Result<()> {
+        self.check_writable()?;
+
         let header_index = page_num / DATA_PAGES_PER_HEADER;
         let page_index = page_num % DATA_PAGES_PER_HEADER;
 
@@ -270,10 +398,9 @@ impl PartitionHandle {
                     // recoveryManager.diskIOHook(vpn);
 
                     Bit::set_bit(header_content.as_mut_slice(), page_index as u32, Bit::Zero)?;
-                    self.master_page.insert(
-                        header_index,
-                        Bit::count_ones(header_content.as_slice()) as u16,
-                    );
+                    let count = Bit::count_ones(header_content.as_slice()) as u16;
+                    self.set_master_page_entry(header_index, count);
+                    self.free_header_hint = self.free_header_hint.min(header_index);
                     self.write_master_page()?;
                     self.write_header_page(header_index)?;
                     Ok(())
@@ -284,10 +411,12 @@ impl PartitionHandle {
 
     /// Frees all DataPages from partition for used.
     pub fn free_data_pages(&mut self) -> Result<()> {
+        self.check_writable()?;
+
         let mut v = vec![];
         for i in 0..MAX_HEADER_PAGE {
             if let Some(v) = self.master_page.get(i) {
-                if *v <= 0 {
+                if *v == 0 {
                     continue;
                 }
             }
@@ -297,15 +426,11 @@ impl PartitionHandle {
                 Some(header_content) => header_content,
             };
 
-            for j in 0..DATA_PAGES_PER_HEADER {
-                if Bit::get_bit(header_content.as_slice(), j as u32)?.eq(&Bit::One) {
-                    // here cannot call `self.free_page()` directly which cannot borrow `*self` as mutable
-                    // because it is also borrowed as immutable.
-                    // the solution is cache the header index and page index in vec,
-                    // and then call `self.free_page()` individually after the loop.
-                    v.push((i, j));
-                }
-            }
+            // here cannot call `self.free_page()` directly which cannot borrow `*self` as mutable
+            // because it is also borrowed as immutable.
+            // the solution is cache the header index and page index in vec,
+            // and then call `self.free_page()` individually after the loop.
+            v.extend(Bit::iter_ones(header_content.as_slice()).map(|j| (i, j as usize)));
         }
 
         for (header_index, page_index) in v.iter() {
@@ -334,7 +459,49 @@ impl PartitionHandle {
             return Ok(Bit::get_bit(v.as_slice(), page_index as u32)?.eq(&Bit::Zero));
         }
 
-        Ok(false)
+        Ok(true)
+    }
+
+    /// Checks that the master page agrees with the header pages, and returns every problem found.
+    ///
+    /// Each master page entry must equal the number of allocated data pages recorded in the
+    /// bitmap of its header page, and every loaded header page must be exactly one page long.
+    pub fn verify(&self) -> Vec<String> {
+        let mut problems = vec![];
+
+        for (i, allocated) in self.master_page.iter().enumerate() {
+            let header_content = self.header_pages.get(i);
+
+            if let Some(header_content) = header_content {
+                if header_content.len() != PAGE_SIZE {
+                    problems.push(format!(
+                        "partition {}: header page {} is {} bytes, expected {}",
+                        self.part_num,
+                        i,
+                        header_content.len(),
+                        PAGE_SIZE
+                    ));
+                }
+            }
+
+            let counted = header_content.map_or(0, |v| Bit::count_ones(v.as_slice()));
+            if *allocated as u32 != counted {
+                problems.push(format!(
+                    "partition {}: master page records {} allocated pages under header page {}, but its bitmap has {}",
+                    self.part_num, allocated, i, counted
+                ));
+            }
+        }
+
+        let total: usize = self.master_page.iter().map(|v| *v as usize).sum();
+        if self.allocated != total {
+            problems.push(format!(
+                "partition {}: {} allocated pages are counted, but the master page records {}",
+                self.part_num, self.allocated, total
+            ));
+        }
+
+        problems
     }
 
     /// Returns the offset in OS file for master page.
@@ -381,3 +548,428 @@ impl PartitionHandle {
         (1 + 1 + previous_headers + page_num) * PAGE_SIZE
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::{tempdir, TempDir};
+
+    struct DummyRecoveryManager;
+
+    impl RecoveryManager for DummyRecoveryManager {}
+
+    fn get_partition(dir: &TempDir, part_num: usize) -> (PartitionHandle, String) {
+        let file_name = dir
+            .path()
+            .join(part_num.to_string())
+            .to_string_lossy()
+            .to_string();
+        let part = PartitionHandle::new(part_num, Box::new(DummyRecoveryManager));
+        (part, file_name)
+    }
+
+    #[test]
+    fn test_alloc_page_persist() -> Result<()> {
+        let dir = tempdir()?;
+
+        let (mut part, file_name) = get_partition(&dir, 0);
+        part.open(file_name.clone())?;
+        assert_eq!(0, part.alloc_page()?);
+        assert_eq!(1, part.alloc_page()?);
+        part.write_page(1, &[7_u8; PAGE_SIZE])?;
+        drop(part);
+
+        let (mut part, _) = get_partition(&dir, 0);
+        part.open(file_name)?;
+        assert!(!part.is_not_allocated_page(0)?);
+        assert!(!part.is_not_allocated_page(1)?);
+        assert!(part.is_not_allocated_page(2)?);
+
+        let mut buf = [0_u8; PAGE_SIZE];
+        part.read_page(1, &mut buf)?;
+        assert_eq!([7_u8; PAGE_SIZE], buf);
+        assert_eq!(2, part.alloc_page()?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_master_page() -> Result<()> {
+        let dir = tempdir()?;
+
+        let (mut part, file_name) = get_partition(&dir, 0);
+        part.open(file_name.clone())?;
+        for _ in 0..3 {
+            part.alloc_page()?;
+        }
+        part.alloc_page_specific(2, 7)?;
+        drop(part);
+
+        // the master page is read into a page-sized buffer, not an empty one
+        let (mut part, _) = get_partition(&dir, 0);
+        part.open(file_name)?;
+        assert_eq!(&[3, 0, 1, 0], &part.master_page[..4]);
+        assert_eq!(3, part.header_pages.len());
+        assert!(part.verify().is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_master_page_entries_in_place() -> Result<()> {
+        let dir = tempdir()?;
+
+        let (mut part, file_name) = get_partition(&dir, 0);
+        part.open(file_name)?;
+        part.alloc_page()?;
+        part.alloc_page_specific(1, 0)?;
+        part.alloc_page()?;
+        part.free_page(0)?;
+
+        // each allocation and free overwrites its entry instead of inserting a new one
+        assert_eq!(MAX_HEADER_PAGE, part.master_page.len());
+        assert_eq!(&[1, 1, 0], &part.master_page[..3]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_unloaded_header_page_not_allocated() -> Result<()> {
+        let dir = tempdir()?;
+
+        let (mut part, file_name) = get_partition(&dir, 0);
+        part.open(file_name.clone())?;
+        part.alloc_page()?;
+        drop(part);
+
+        // claim 1 page under header page 1 on disk, while the file ends before header page 1
+        let file = OpenOptions::new().write(true).open(&file_name)?;
+        file.write_at(&[0_u8, 1, 0, 1], 0)?;
+        drop(file);
+
+        let (mut part, _) = get_partition(&dir, 0);
+        part.open(file_name)?;
+        assert_eq!(1, part.header_pages.len());
+        assert!(part.is_not_allocated_page(DATA_PAGES_PER_HEADER)?);
+
+        let mut buf = [0_u8; PAGE_SIZE];
+        assert!(part.read_page(DATA_PAGES_PER_HEADER, &mut buf).is_err());
+        assert!(part.write_page(DATA_PAGES_PER_HEADER, &buf).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_open_read_only() -> Result<()> {
+        let dir = tempdir()?;
+
+        let (mut part, file_name) = get_partition(&dir, 0);
+        part.open(file_name.clone())?;
+        let page_num = part.alloc_page()?;
+        part.write_page(page_num, &[1_u8; PAGE_SIZE])?;
+        drop(part);
+
+        let (mut part, _) = get_partition(&dir, 0);
+        part.open_read_only(file_name)?;
+        assert!(part.is_read_only());
+
+        let mut buf = [0_u8; PAGE_SIZE];
+        part.read_page(page_num, &mut buf)?;
+        assert_eq!([1_u8; PAGE_SIZE], buf);
+
+        let read_only = Some(&DBError::ReadOnlyError(0));
+        assert_eq!(read_only, part.alloc_page().unwrap_err().downcast_ref());
+        assert_eq!(
+            read_only,
+            part.alloc_page_specific(0, 5).unwrap_err().downcast_ref()
+        );
+        assert_eq!(
+            read_only,
+            part.write_page(page_num, &buf).unwrap_err().downcast_ref()
+        );
+        assert_eq!(
+            read_only,
+            part.free_page(page_num).unwrap_err().downcast_ref()
+        );
+        assert_eq!(
+            read_only,
+            part.free_data_pages().unwrap_err().downcast_ref()
+        );
+        assert!(!part.is_not_allocated_page(page_num)?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_alloc_and_free_pages() -> Result<()> {
+        let dir = tempdir()?;
+
+        let (mut part, file_name) = get_partition(&dir, 0);
+        part.open(file_name)?;
+        for i in 0..20 {
+            assert_eq!(i, part.alloc_page()?);
+        }
+
+        // freed pages are reused from the lowest page number
+        part.free_page(13)?;
+        part.free_page(2)?;
+        assert!(part.is_not_allocated_page(2)?);
+        assert_eq!(2, part.alloc_page()?);
+        assert_eq!(13, part.alloc_page()?);
+        assert_eq!(20, part.alloc_page()?);
+
+        assert_eq!(DATA_PAGES_PER_HEADER + 3, part.alloc_page_specific(1, 3)?);
+        assert!(part.alloc_page_specific(1, 3).is_err());
+
+        // out of range indices are rejected without loading any header page
+        let out_of_range =
+            |e: anyhow::Error| matches!(e.downcast_ref(), Some(DBError::IllegalArgumentError(_)));
+        assert!(out_of_range(
+            part.alloc_page_specific(MAX_HEADER_PAGE, 0).unwrap_err()
+        ));
+        assert!(out_of_range(
+            part.alloc_page_specific(usize::MAX, 0).unwrap_err()
+        ));
+        assert!(out_of_range(
+            part.alloc_page_specific(0, DATA_PAGES_PER_HEADER)
+                .unwrap_err()
+        ));
+        assert_eq!(2, part.header_pages.len());
+
+        part.free_data_pages()?;
+        assert!((0..=20).all(|i| part.is_not_allocated_page(i).unwrap()));
+        assert!(part.is_not_allocated_page(DATA_PAGES_PER_HEADER + 3)?);
+        assert!(part.master_page.iter().all(|v| *v == 0));
+        assert!(part.verify().is_empty());
+        assert_eq!(0, part.alloc_page()?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_alloc_page_skips_full_header_pages() -> Result<()> {
+        let dir = tempdir()?;
+
+        let (mut part, file_name) = get_partition(&dir, 0);
+        part.open(file_name)?;
+        assert_eq!(0, part.alloc_page()?);
+
+        // fill up header page 0 without allocating every data page one by one
+        part.header_pages[0] = vec![0xFF; PAGE_SIZE];
+        part.set_master_page_entry(0, DATA_PAGES_PER_HEADER as u16);
+        assert_eq!(DATA_PAGES_PER_HEADER, part.alloc_page()?);
+        assert_eq!(1, part.free_header_hint);
+        assert_eq!(DATA_PAGES_PER_HEADER + 1, part.alloc_page()?);
+
+        // freeing a page under an earlier header page makes it the first choice again
+        part.free_page(5)?;
+        assert_eq!(0, part.free_header_hint);
+        assert_eq!(5, part.alloc_page()?);
+        assert_eq!(DATA_PAGES_PER_HEADER + 2, part.alloc_page()?);
+
+        // a full partition keeps failing without rescanning the master page
+        part.master_page = vec![DATA_PAGES_PER_HEADER as u16; MAX_HEADER_PAGE];
+        part.free_header_hint = 0;
+        assert!(part.alloc_page().is_err());
+        assert_eq!(MAX_HEADER_PAGE, part.free_header_hint);
+        assert!(part.alloc_page().is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_reopen_handle_on_new_file() -> Result<()> {
+        let dir = tempdir()?;
+
+        let (mut part, file_name) = get_partition(&dir, 0);
+        part.open(file_name.clone())?;
+        for _ in 0..3 {
+            part.alloc_page()?;
+        }
+        part.alloc_page_specific(1, 0)?;
+        part.set_master_page_entry(0, DATA_PAGES_PER_HEADER as u16);
+        part.alloc_page()?;
+        assert_eq!(1, part.free_header_hint);
+
+        // nothing of the previous file is kept when the handle opens a new one
+        let other_file_name = dir.path().join("other").to_string_lossy().to_string();
+        part.open(other_file_name.clone())?;
+        assert!(part.master_page.iter().all(|v| *v == 0));
+        assert!(part.header_pages.is_empty());
+        assert_eq!(0, part.free_header_hint);
+        assert_eq!(0, part.allocated_pages());
+        assert_eq!(0, part.alloc_page()?);
+        drop(part);
+
+        let (mut part, _) = get_partition(&dir, 0);
+        part.open(other_file_name)?;
+        assert_eq!(1, part.allocated_pages());
+        assert_eq!(1, part.header_pages.len());
+        assert!(part.verify().is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_alloc_extent() -> Result<()> {
+        let dir = tempdir()?;
+
+        let (mut part, file_name) = get_partition(&dir, 0);
+        part.open(file_name)?;
+        assert_eq!(0, part.alloc_extent(64)?);
+        assert_eq!(64, part.alloc_page()?);
+        assert_eq!(65, part.alloc_extent(8)?);
+        assert!((0..73).all(|i| !part.is_not_allocated_page(i).unwrap()));
+
+        // an extent only reuses a free range that is long enough
+        for i in 10..14 {
+            part.free_page(i)?;
+        }
+        assert_eq!(73, part.alloc_extent(5)?);
+        assert_eq!(10, part.alloc_extent(4)?);
+
+        // an extent never spans two header pages
+        part.alloc_page_specific(0, DATA_PAGES_PER_HEADER - 1)?;
+        assert_eq!(
+            DATA_PAGES_PER_HEADER,
+            part.alloc_extent(DATA_PAGES_PER_HEADER - 10)?
+        );
+        assert!(part.verify().is_empty());
+
+        assert!(part.alloc_extent(0).is_err());
+        assert!(part.alloc_extent(DATA_PAGES_PER_HEADER + 1).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_page_quota() -> Result<()> {
+        let dir = tempdir()?;
+
+        let (mut part, file_name) = get_partition(&dir, 3);
+        part.open(file_name.clone())?;
+        part.set_page_quota(Some(10));
+        assert_eq!(0, part.alloc_extent(8)?);
+        assert_eq!(8, part.alloc_page()?);
+        assert_eq!(9, part.allocated_pages());
+
+        let quota_exceeded = Some(&DBError::QuotaExceededError(3, 10));
+        assert_eq!(
+            quota_exceeded,
+            part.alloc_extent(2).unwrap_err().downcast_ref()
+        );
+        assert_eq!(9, part.alloc_page()?);
+        assert_eq!(
+            quota_exceeded,
+            part.alloc_page().unwrap_err().downcast_ref()
+        );
+        assert_eq!(
+            quota_exceeded,
+            part.alloc_page_specific(1, 0).unwrap_err().downcast_ref()
+        );
+
+        // freeing pages makes room again, and a lower quota only blocks new allocations
+        part.free_page(4)?;
+        assert_eq!(4, part.alloc_page()?);
+        part.set_page_quota(Some(5));
+        assert_eq!(10, part.allocated_pages());
+        assert!(part.alloc_page().is_err());
+        part.set_page_quota(None);
+        assert_eq!(10, part.alloc_page()?);
+        part.free_data_pages()?;
+        assert_eq!(0, part.allocated_pages());
+        part.alloc_extent(3)?;
+        assert!(part.verify().is_empty());
+        drop(part);
+
+        // the allocated page count is restored from the master page on open
+        let (mut part, _) = get_partition(&dir, 3);
+        part.open(file_name)?;
+        assert_eq!(3, part.allocated_pages());
+        part.set_page_quota(Some(4));
+        assert_eq!(3, part.alloc_page()?);
+        assert!(part.alloc_page().is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_verify() -> Result<()> {
+        let dir = tempdir()?;
+
+        let (mut part, file_name) = get_partition(&dir, 0);
+        part.open(file_name.clone())?;
+        for _ in 0..10 {
+            part.alloc_page()?;
+        }
+        part.free_page(3)?;
+        assert!(part.verify().is_empty());
+        drop(part);
+
+        // claim 12 pages under header page 0 and 1 page under header page 1 on disk
+        let file = OpenOptions::new().write(true).open(&file_name)?;
+        file.write_at(&[0_u8, 12, 0, 1], 0)?;
+        drop(file);
+
+        let (mut part, _) = get_partition(&dir, 0);
+        part.open_read_only(file_name)?;
+        let problems = part.verify();
+        assert_eq!(2, problems.len());
+        assert!(problems[0]
+            .contains("records 12 allocated pages under header page 0, but its bitmap has 9"));
+        assert!(problems[1]
+            .contains("records 1 allocated pages under header page 1, but its bitmap has 0"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_allocated_pages_mismatched_master_page() -> Result<()> {
+        let dir = tempdir()?;
+
+        let (mut part, file_name) = get_partition(&dir, 0);
+        part.open(file_name.clone())?;
+        for _ in 0..9 {
+            part.alloc_page()?;
+        }
+        drop(part);
+
+        // claim 12 pages under header page 0 on disk, while its bitmap has 9
+        let file = OpenOptions::new().write(true).open(&file_name)?;
+        file.write_at(&[0_u8, 12], 0)?;
+        drop(file);
+
+        let (mut part, _) = get_partition(&dir, 0);
+        part.open(file_name.clone())?;
+        assert_eq!(12, part.allocated_pages());
+        assert_eq!(9, part.alloc_page()?);
+        assert_eq!(10, part.allocated_pages());
+        assert!(part.verify().is_empty());
+        drop(part);
+
+        // claim no pages under header page 0 on disk, while its bitmap has 10
+        let file = OpenOptions::new().write(true).open(&file_name)?;
+        file.write_at(&[0_u8, 0], 0)?;
+        drop(file);
+
+        let (mut part, _) = get_partition(&dir, 0);
+        part.open(file_name)?;
+        assert_eq!(0, part.allocated_pages());
+        part.free_page(0)?;
+        assert_eq!(9, part.allocated_pages());
+        assert!(part.verify().is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_open_read_only_missing_file() -> Result<()> {
+        let dir = tempdir()?;
+
+        let (mut part, file_name) = get_partition(&dir, 0);
+        assert!(part.open_read_only(file_name).is_err());
+        assert!(!dir.path().join("0").exists());
+
+        Ok(())
+    }
+}
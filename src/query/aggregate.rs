@@ -0,0 +1,375 @@
+use crate::common::error::DBError;
+use crate::databox::{DataBox, DataType};
+use anyhow::Result;
+use std::any::Any;
+use std::cmp::Ordering;
+
+/// Constructs an aggregator in its initial state.
+pub type AggregateFactory = fn() -> Box<dyn Aggregator>;
+
+/// Incremental computation of an aggregate function, e.g. `SUM(salary)`, over the rows of a group.
+///
+/// Partial aggregates computed over disjoint parts of a group, e.g. by parallel workers or
+/// by the runs of an external sort, are combined with `merge()` before calling `finish()`.
+pub trait Aggregator: Send {
+    /// Resets the aggregator to its initial state.
+    fn init(&mut self);
+
+    /// Adds the value of the next row.
+    fn accumulate(&mut self, v: &DataBox) -> Result<()>;
+
+    /// Adds the partial state of another aggregator of the same type.
+    fn merge(&mut self, other: &dyn Aggregator) -> Result<()>;
+
+    /// Returns the aggregate of every value seen so far.
+    fn finish(&self) -> Result<DataBox>;
+
+    /// Returns the aggregator as `Any`, implemented as `self`, so that `merge()` can downcast `other`.
+    fn as_any(&self) -> &dyn Any;
+}
+
+/// Returns `other` as an aggregator of the same type as `A`, for implementing `merge()`.
+pub fn downcast_aggregator<A: Aggregator + 'static>(other: &dyn Aggregator) -> Result<&A> {
+    other.as_any().downcast_ref::<A>().ok_or_else(|| {
+        DBError::IllegalArgumentError("cannot merge aggregators of different types").into()
+    })
+}
+
+/// Adds two numeric values, summing integers as longs.
+fn add(a: DataBox, b: DataBox) -> Result<DataBox> {
+    match (a, b) {
+        (DataBox::Long(a), DataBox::Long(b)) => a
+            .checked_add(b)
+            .map(DataBox::Long)
+            .ok_or_else(|| DBError::IllegalArgumentError("integer overflow in SUM").into()),
+        (a, b) => Ok(DataBox::Float(float(&a)? + float(&b)?)),
+    }
+}
+
+/// Checks that `v` is numeric, and widens integers to longs.
+fn numeric(v: &DataBox) -> Result<DataBox> {
+    match v {
+        DataBox::Integer(i) => Ok(DataBox::Long(i64::from(*i))),
+        DataBox::Long(_) | DataBox::Float(_) => Ok(v.clone()),
+        _ => Err(DBError::TypeError(v.clone(), "numeric").into()),
+    }
+}
+
+/// Widens a numeric value to a float, rounding longs that a float cannot hold exactly.
+///
+/// _Note_: Unlike `DataBox::cast()`, which refuses lossy casts, rounding is expected when
+/// aggregating, e.g. for `AVG` or for the `SUM` of longs and floats.
+fn float(v: &DataBox) -> Result<f64> {
+    match v {
+        DataBox::Integer(i) => Ok(*i as f64),
+        DataBox::Long(l) => Ok(*l as f64),
+        DataBox::Float(f) => Ok(*f),
+        _ => Err(DBError::TypeError(v.clone(), "numeric").into()),
+    }
+}
+
+/// Compares two non-NULL values, promoting numeric values of different types.
+fn compare(a: &DataBox, b: &DataBox) -> Result<Ordering> {
+    let (ta, tb) = (a.datatype(), b.datatype());
+    let ordering = match ta.zip(tb).and_then(|(ta, tb)| ta.promote(tb)) {
+        Some(DataType::Float) if ta != tb => float(a)?.partial_cmp(&float(b)?),
+        Some(_) if ta != tb => numeric(a)?.partial_cmp(&numeric(b)?),
+        _ if std::mem::discriminant(a) == std::mem::discriminant(b) => a.partial_cmp(b),
+        _ => return Err(DBError::TypeError(b.clone(), "comparable value").into()),
+    };
+    // NaN is ordered after every other float
+    Ok(ordering.unwrap_or_else(|| match (a, b) {
+        (DataBox::Float(a), _) if a.is_nan() => Ordering::Greater,
+        _ => Ordering::Less,
+    }))
+}
+
+/// `COUNT(v)`: number of non-NULL values.
+#[derive(Default)]
+pub struct CountAggregator {
+    count: i64,
+}
+
+impl Aggregator for CountAggregator {
+    fn init(&mut self) {
+        self.count = 0;
+    }
+
+    fn accumulate(&mut self, v: &DataBox) -> Result<()> {
+        if *v != DataBox::Null {
+            self.count += 1;
+        }
+        Ok(())
+    }
+
+    fn merge(&mut self, other: &dyn Aggregator) -> Result<()> {
+        self.count += downcast_aggregator::<Self>(other)?.count;
+        Ok(())
+    }
+
+    fn finish(&self) -> Result<DataBox> {
+        Ok(DataBox::Long(self.count))
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// `SUM(v)`: sum of non-NULL numeric values as a long, or as a float if any value is a float.
+/// NULL if there are no values.
+#[derive(Default)]
+pub struct SumAggregator {
+    sum: Option<DataBox>,
+}
+
+impl Aggregator for SumAggregator {
+    fn init(&mut self) {
+        self.sum = None;
+    }
+
+    fn accumulate(&mut self, v: &DataBox) -> Result<()> {
+        if *v == DataBox::Null {
+            return Ok(());
+        }
+        let v = numeric(v)?;
+        self.sum = Some(match self.sum.take() {
+            None => v,
+            Some(sum) => add(sum, v)?,
+        });
+        Ok(())
+    }
+
+    fn merge(&mut self, other: &dyn Aggregator) -> Result<()> {
+        match &downcast_aggregator::<Self>(other)?.sum {
+            None => Ok(()),
+            Some(v) => self.accumulate(v),
+        }
+    }
+
+    fn finish(&self) -> Result<DataBox> {
+        Ok(self.sum.clone().unwrap_or(DataBox::Null))
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// `AVG(v)`: mean of non-NULL numeric values as a float, NULL if there are no values.
+#[derive(Default)]
+pub struct AvgAggregator {
+    sum: f64,
+    count: i64,
+}
+
+impl Aggregator for AvgAggregator {
+    fn init(&mut self) {
+        self.sum = 0.0;
+        self.count = 0;
+    }
+
+    fn accumulate(&mut self, v: &DataBox) -> Result<()> {
+        if *v == DataBox::Null {
+            return Ok(());
+        }
+        self.sum += float(v)?;
+        self.count += 1;
+        Ok(())
+    }
+
+    fn merge(&mut self, other: &dyn Aggregator) -> Result<()> {
+        let other = downcast_aggregator::<Self>(other)?;
+        self.sum += other.sum;
+        self.count += other.count;
+        Ok(())
+    }
+
+    fn finish(&self) -> Result<DataBox> {
+        match self.count {
+            0 => Ok(DataBox::Null),
+            count => Ok(DataBox::Float(self.sum / count as f64)),
+        }
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// `MIN(v)` and `MAX(v)`: smallest or largest non-NULL value, NULL if there are no values.
+pub struct ExtremumAggregator {
+    /// `Ordering::Less` for `MIN`, `Ordering::Greater` for `MAX`
+    keep: Ordering,
+    value: Option<DataBox>,
+}
+
+impl ExtremumAggregator {
+    pub fn min() -> Self {
+        Self {
+            keep: Ordering::Less,
+            value: None,
+        }
+    }
+
+    pub fn max() -> Self {
+        Self {
+            keep: Ordering::Greater,
+            value: None,
+        }
+    }
+}
+
+impl Aggregator for ExtremumAggregator {
+    fn init(&mut self) {
+        self.value = None;
+    }
+
+    fn accumulate(&mut self, v: &DataBox) -> Result<()> {
+        if *v == DataBox::Null {
+            return Ok(());
+        }
+        match &self.value {
+            Some(value) if compare(v, value)? != self.keep => {}
+            _ => self.value = Some(v.clone()),
+        }
+        Ok(())
+    }
+
+    fn merge(&mut self, other: &dyn Aggregator) -> Result<()> {
+        match &downcast_aggregator::<Self>(other)?.value {
+            None => Ok(()),
+            Some(v) => self.accumulate(v),
+        }
+    }
+
+    fn finish(&self) -> Result<DataBox> {
+        Ok(self.value.clone().unwrap_or(DataBox::Null))
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn aggregate(mut agg: Box<dyn Aggregator>, values: &[DataBox]) -> Result<DataBox> {
+        for v in values {
+            agg.accumulate(v)?;
+        }
+        agg.finish()
+    }
+
+    #[test]
+    fn test_builtin_aggregators() -> Result<()> {
+        let values = [
+            DataBox::Integer(3),
+            DataBox::Null,
+            DataBox::Long(-5),
+            DataBox::Integer(10),
+        ];
+
+        let count = aggregate(Box::<CountAggregator>::default(), &values)?;
+        assert_eq!(DataBox::Long(3), count);
+        let sum = aggregate(Box::<SumAggregator>::default(), &values)?;
+        assert_eq!(DataBox::Long(8), sum);
+        let avg = aggregate(Box::<AvgAggregator>::default(), &values)?;
+        assert_eq!(DataBox::Float(8.0 / 3.0), avg);
+        let min = aggregate(Box::new(ExtremumAggregator::min()), &values)?;
+        assert_eq!(DataBox::Long(-5), min);
+        let max = aggregate(Box::new(ExtremumAggregator::max()), &values)?;
+        assert_eq!(DataBox::Integer(10), max);
+
+        let sum = aggregate(Box::<SumAggregator>::default(), &[1.into(), 0.5.into()])?;
+        assert_eq!(DataBox::Float(1.5), sum);
+        let max = aggregate(
+            Box::new(ExtremumAggregator::max()),
+            &["b".into(), "ab".into()],
+        )?;
+        assert_eq!(DataBox::from("b"), max);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_empty_group() -> Result<()> {
+        assert_eq!(
+            DataBox::Long(0),
+            aggregate(Box::<CountAggregator>::default(), &[DataBox::Null])?
+        );
+        assert_eq!(
+            DataBox::Null,
+            aggregate(Box::<SumAggregator>::default(), &[])?
+        );
+        assert_eq!(
+            DataBox::Null,
+            aggregate(Box::<AvgAggregator>::default(), &[])?
+        );
+        assert_eq!(
+            DataBox::Null,
+            aggregate(Box::new(ExtremumAggregator::min()), &[])?
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_merge_and_init() -> Result<()> {
+        let mut a = SumAggregator::default();
+        let mut b = SumAggregator::default();
+        a.accumulate(&1.into())?;
+        b.accumulate(&2.into())?;
+        a.merge(&b)?;
+        assert_eq!(DataBox::Long(3), a.finish()?);
+
+        a.merge(&SumAggregator::default())?;
+        assert_eq!(DataBox::Long(3), a.finish()?);
+        assert!(a.merge(&CountAggregator::default()).is_err());
+
+        a.init();
+        assert_eq!(DataBox::Null, a.finish()?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_long_above_float_precision() -> Result<()> {
+        // 2^53 + 1 is the smallest long that a float cannot hold exactly
+        let long = DataBox::Long((1 << 53) + 1);
+
+        let avg = aggregate(Box::<AvgAggregator>::default(), std::slice::from_ref(&long))?;
+        assert_eq!(DataBox::Float(9007199254740992.0), avg);
+        let sum = aggregate(
+            Box::<SumAggregator>::default(),
+            &[long.clone(), DataBox::Float(1.0)],
+        )?;
+        assert_eq!(DataBox::Float(9007199254740992.0), sum);
+        let max = aggregate(
+            Box::new(ExtremumAggregator::max()),
+            &[DataBox::Float(1.0), long.clone()],
+        )?;
+        assert_eq!(long, max);
+        let min = aggregate(
+            Box::new(ExtremumAggregator::min()),
+            &[long.clone(), DataBox::Float(1.0)],
+        )?;
+        assert_eq!(DataBox::Float(1.0), min);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_values() {
+        let mut sum = SumAggregator::default();
+        assert!(sum.accumulate(&"1".into()).is_err());
+        sum.accumulate(&DataBox::Long(i64::MAX)).unwrap();
+        assert!(sum.accumulate(&1.into()).is_err());
+
+        let mut max = ExtremumAggregator::max();
+        max.accumulate(&1.into()).unwrap();
+        assert!(max.accumulate(&"a".into()).is_err());
+    }
+}
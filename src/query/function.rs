@@ -0,0 +1,459 @@
+use crate::common::error::DBError;
+use crate::databox::{DataBox, DataType};
+use crate::query::{
+    AggregateFactory, Aggregator, AvgAggregator, CountAggregator, ExtremumAggregator, SumAggregator,
+};
+use anyhow::Result;
+use std::collections::HashMap;
+
+/// Signature of a scalar function, which maps its evaluated arguments to a single value.
+pub type ScalarFn = fn(&[DataBox]) -> Result<DataBox>;
+
+/// A named scalar function callable from SQL expressions, e.g. `UPPER(name)`.
+pub struct ScalarFunction {
+    /// Function name in upper case
+    name: String,
+    /// Minimum number of arguments
+    min_args: usize,
+    /// Maximum number of arguments, `None` if the function is variadic
+    max_args: Option<usize>,
+    /// Whether the function returns NULL as soon as any argument is NULL, without being called
+    strict: bool,
+    /// Whether the function always returns the same value for the same arguments,
+    /// so that plans calling it can be cached and constant calls folded
+    deterministic: bool,
+    /// Function body
+    func: ScalarFn,
+}
+
+impl ScalarFunction {
+    pub fn new(name: &str, min_args: usize, max_args: Option<usize>, func: ScalarFn) -> Self {
+        Self {
+            name: name.to_uppercase(),
+            min_args,
+            max_args,
+            strict: true,
+            deterministic: false,
+            func,
+        }
+    }
+
+    /// Makes the function receive NULL arguments instead of returning NULL for them.
+    pub fn non_strict(mut self) -> Self {
+        self.strict = false;
+        self
+    }
+
+    /// Marks the function as deterministic, functions are treated as volatile otherwise.
+    pub fn deterministic(mut self) -> Self {
+        self.deterministic = true;
+        self
+    }
+
+    pub fn is_deterministic(&self) -> bool {
+        self.deterministic
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Calls the function, or returns `DBError::ArityError` if the number of arguments is not accepted.
+    pub fn call(&self, args: &[DataBox]) -> Result<DataBox> {
+        if args.len() < self.min_args || self.max_args.is_some_and(|max| args.len() > max) {
+            return Err(DBError::ArityError(self.name.clone(), args.len()).into());
+        }
+
+        if self.strict && args.contains(&DataBox::Null) {
+            Ok(DataBox::Null)
+        } else {
+            (self.func)(args)
+        }
+    }
+}
+
+/// Registry of the scalar and aggregate functions known to the expression engine,
+/// looked up case-insensitively.
+pub struct FunctionRegistry {
+    functions: HashMap<String, ScalarFunction>,
+    aggregates: HashMap<String, AggregateFactory>,
+}
+
+impl Default for FunctionRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FunctionRegistry {
+    /// Construct a registry holding the built-in functions.
+    pub fn new() -> Self {
+        let mut registry = Self {
+            functions: HashMap::new(),
+            aggregates: HashMap::new(),
+        };
+
+        registry
+            .register_builtin(ScalarFunction::new("LENGTH", 1, Some(1), length).deterministic());
+        registry
+            .register_builtin(ScalarFunction::new("SUBSTR", 2, Some(3), substr).deterministic());
+        registry.register_builtin(ScalarFunction::new("UPPER", 1, Some(1), upper).deterministic());
+        registry.register_builtin(ScalarFunction::new("LOWER", 1, Some(1), lower).deterministic());
+        registry.register_builtin(ScalarFunction::new("TRIM", 1, Some(1), trim).deterministic());
+        registry.register_builtin(
+            ScalarFunction::new("CONCAT", 1, None, concat)
+                .non_strict()
+                .deterministic(),
+        );
+        registry
+            .register_builtin(ScalarFunction::new("REPLACE", 3, Some(3), replace).deterministic());
+
+        registry
+            .aggregates
+            .insert("COUNT".to_string(), || Box::<CountAggregator>::default());
+        registry
+            .aggregates
+            .insert("SUM".to_string(), || Box::<SumAggregator>::default());
+        registry
+            .aggregates
+            .insert("AVG".to_string(), || Box::<AvgAggregator>::default());
+        registry
+            .aggregates
+            .insert("MIN".to_string(), || Box::new(ExtremumAggregator::min()));
+        registry
+            .aggregates
+            .insert("MAX".to_string(), || Box::new(ExtremumAggregator::max()));
+
+        registry
+    }
+
+    fn contains(&self, name: &str) -> bool {
+        self.functions.contains_key(name) || self.aggregates.contains_key(name)
+    }
+
+    fn register_builtin(&mut self, function: ScalarFunction) {
+        self.functions.insert(function.name.clone(), function);
+    }
+
+    /// Adds a function, or returns `DBError::DuplicateFunctionError` if one with the same name
+    /// is already registered.
+    pub fn register(&mut self, function: ScalarFunction) -> Result<()> {
+        if self.contains(&function.name) {
+            return Err(DBError::DuplicateFunctionError(function.name).into());
+        }
+        self.register_builtin(function);
+        Ok(())
+    }
+
+    /// Adds a volatile, strict function taking exactly `arity` arguments.
+    /// Use `register()` with a `ScalarFunction` to register a deterministic or variadic function.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// registry.register_function("double", 1, |args| Ok(DataBox::Integer(args[0].clone().integer()? * 2)))?;
+    /// let v = registry.call("DOUBLE", &[DataBox::Integer(21)])?; // DataBox::Integer(42)
+    /// ```
+    pub fn register_function(&mut self, name: &str, arity: usize, func: ScalarFn) -> Result<()> {
+        self.register(ScalarFunction::new(name, arity, Some(arity), func))
+    }
+
+    /// Adds an aggregate function, or returns `DBError::DuplicateFunctionError` if a function
+    /// with the same name is already registered.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// registry.register_aggregate("median", || Box::new(MedianAggregator::default()))?;
+    /// let mut agg = registry.aggregator("MEDIAN")?;
+    /// ```
+    pub fn register_aggregate(&mut self, name: &str, factory: AggregateFactory) -> Result<()> {
+        let name = name.to_uppercase();
+        if self.contains(&name) {
+            return Err(DBError::DuplicateFunctionError(name).into());
+        }
+        self.aggregates.insert(name, factory);
+        Ok(())
+    }
+
+    /// Returns a new aggregator in its initial state for the aggregate function with the given name,
+    /// or `DBError::UnknownFunctionError` if there is none.
+    pub fn aggregator(&self, name: &str) -> Result<Box<dyn Aggregator>> {
+        match self.aggregates.get(&name.to_uppercase()) {
+            None => Err(DBError::UnknownFunctionError(name.to_string()).into()),
+            Some(factory) => Ok(factory()),
+        }
+    }
+
+    /// Returns the function with the given name, ignoring case.
+    pub fn get(&self, name: &str) -> Option<&ScalarFunction> {
+        self.functions.get(&name.to_uppercase())
+    }
+
+    /// Calls the function with the given name, or returns `DBError::UnknownFunctionError` if there is none.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let registry = FunctionRegistry::new();
+    /// let v = registry.call("upper", &[DataBox::from("rookie")]).unwrap(); // DataBox::String("ROOKIE")
+    /// ```
+    pub fn call(&self, name: &str, args: &[DataBox]) -> Result<DataBox> {
+        match self.get(name) {
+            None => Err(DBError::UnknownFunctionError(name.to_string()).into()),
+            Some(function) => function.call(args),
+        }
+    }
+}
+
+fn string_arg(v: &DataBox) -> Result<String> {
+    Ok(v.clone().string()?)
+}
+
+fn integer_arg(v: &DataBox) -> Result<i32> {
+    Ok(v.clone().cast(DataType::Integer)?.integer()?)
+}
+
+/// `LENGTH(s)`: number of characters of a string, or number of bytes of a byte array.
+fn length(args: &[DataBox]) -> Result<DataBox> {
+    let len = match &args[0] {
+        DataBox::ByteArray(v) => v.len(),
+        v => string_arg(v)?.chars().count(),
+    };
+    Ok(DataBox::Integer(len as i32))
+}
+
+/// `SUBSTR(s, start[, len])`: `len` characters of `s` from the 1-based position `start`,
+/// or every character from `start` if `len` is omitted.
+fn substr(args: &[DataBox]) -> Result<DataBox> {
+    let s = string_arg(&args[0])?;
+    let start = integer_arg(&args[1])? as i64;
+    let end = match args.get(2) {
+        None => i64::MAX,
+        Some(len) => match integer_arg(len)? {
+            len if len < 0 => {
+                return Err(DBError::IllegalArgumentError("negative substring length").into())
+            }
+            len => start + len as i64,
+        },
+    };
+
+    // positions before 1 still consume `len`, as in standard SQL
+    let skip = (start - 1).max(0) as usize;
+    let take = (end - start.max(1)).max(0) as usize;
+    Ok(DataBox::String(s.chars().skip(skip).take(take).collect()))
+}
+
+/// `UPPER(s)`
+fn upper(args: &[DataBox]) -> Result<DataBox> {
+    Ok(DataBox::String(string_arg(&args[0])?.to_uppercase()))
+}
+
+/// `LOWER(s)`
+fn lower(args: &[DataBox]) -> Result<DataBox> {
+    Ok(DataBox::String(string_arg(&args[0])?.to_lowercase()))
+}
+
+/// `TRIM(s)`: `s` without leading and trailing whitespace.
+fn trim(args: &[DataBox]) -> Result<DataBox> {
+    Ok(DataBox::String(string_arg(&args[0])?.trim().to_string()))
+}
+
+/// `CONCAT(v, ...)`: text of every argument joined together, skipping NULLs.
+fn concat(args: &[DataBox]) -> Result<DataBox> {
+    let mut s = String::new();
+    for v in args {
+        match v {
+            DataBox::Null => {}
+            v => s.push_str(&string_arg(&v.clone().cast(DataType::String(usize::MAX))?)?),
+        }
+    }
+    Ok(DataBox::String(s))
+}
+
+/// `REPLACE(s, from, to)`: `s` with every occurrence of `from` replaced by `to`.
+fn replace(args: &[DataBox]) -> Result<DataBox> {
+    let s = string_arg(&args[0])?;
+    let from = string_arg(&args[1])?;
+    let to = string_arg(&args[2])?;
+    if from.is_empty() {
+        Ok(DataBox::String(s))
+    } else {
+        Ok(DataBox::String(s.replace(&from, &to)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn call(name: &str, args: &[DataBox]) -> DataBox {
+        FunctionRegistry::new().call(name, args).unwrap()
+    }
+
+    #[test]
+    fn test_length() {
+        assert_eq!(
+            DataBox::Integer(6),
+            call("LENGTH", &[DataBox::from("rookie")])
+        );
+        assert_eq!(
+            DataBox::Integer(2),
+            call("length", &[DataBox::from("数据")])
+        );
+        assert_eq!(
+            DataBox::Integer(3),
+            call("Length", &[DataBox::from(vec![0_u8; 3])])
+        );
+        assert_eq!(DataBox::Null, call("LENGTH", &[DataBox::Null]));
+    }
+
+    #[test]
+    fn test_substr() {
+        let s = DataBox::from("rookiedb");
+        let substr = |args: &[DataBox]| call("SUBSTR", &[std::slice::from_ref(&s), args].concat());
+
+        assert_eq!(DataBox::from("rookie"), substr(&[1.into(), 6.into()]));
+        assert_eq!(DataBox::from("db"), substr(&[7.into()]));
+        assert_eq!(DataBox::from("db"), substr(&[DataBox::Long(7)]));
+        assert_eq!(DataBox::from("ki"), substr(&[4.into(), 2.into()]));
+        assert_eq!(DataBox::from("r"), substr(&[0.into(), 2.into()]));
+        assert_eq!(DataBox::from(""), substr(&[(-3).into(), 2.into()]));
+        assert_eq!(DataBox::from(""), substr(&[20.into()]));
+        assert_eq!(DataBox::from(""), substr(&[3.into(), 0.into()]));
+        assert_eq!(DataBox::Null, substr(&[DataBox::Null]));
+
+        let registry = FunctionRegistry::new();
+        assert!(registry
+            .call("SUBSTR", &[s.clone(), 1.into(), (-1).into()])
+            .is_err());
+        assert!(registry.call("SUBSTR", &[s, "x".into()]).is_err());
+    }
+
+    #[test]
+    fn test_case_and_trim() {
+        assert_eq!(
+            DataBox::from("ROOKIE DB"),
+            call("UPPER", &[DataBox::from("Rookie db")])
+        );
+        assert_eq!(
+            DataBox::from("rookie db"),
+            call("LOWER", &[DataBox::from("Rookie DB")])
+        );
+        assert_eq!(
+            DataBox::from("rookie db"),
+            call("TRIM", &[DataBox::from(" \trookie db\n ")])
+        );
+    }
+
+    #[test]
+    fn test_concat() {
+        assert_eq!(
+            DataBox::from("rookie42TRUE"),
+            call(
+                "CONCAT",
+                &[
+                    DataBox::from("rookie"),
+                    DataBox::Null,
+                    DataBox::Integer(42),
+                    DataBox::Boolean(true)
+                ]
+            )
+        );
+        assert_eq!(DataBox::from(""), call("CONCAT", &[DataBox::Null]));
+    }
+
+    #[test]
+    fn test_replace() {
+        let args = |from: &str, to: &str| [DataBox::from("a-b-c"), from.into(), to.into()];
+
+        assert_eq!(DataBox::from("a+b+c"), call("REPLACE", &args("-", "+")));
+        assert_eq!(DataBox::from("abc"), call("REPLACE", &args("-", "")));
+        assert_eq!(DataBox::from("a-b-c"), call("REPLACE", &args("", "+")));
+    }
+
+    #[test]
+    fn test_register_function() -> Result<()> {
+        let mut registry = FunctionRegistry::new();
+        registry.register_function("double", 1, |args| {
+            Ok(DataBox::Integer(args[0].clone().integer()? * 2))
+        })?;
+        registry.register(
+            ScalarFunction::new("coalesce", 1, None, |args| {
+                Ok(args
+                    .iter()
+                    .find(|v| **v != DataBox::Null)
+                    .cloned()
+                    .unwrap_or(DataBox::Null))
+            })
+            .non_strict()
+            .deterministic(),
+        )?;
+
+        assert_eq!(DataBox::Integer(42), registry.call("DOUBLE", &[21.into()])?);
+        assert_eq!(DataBox::Null, registry.call("double", &[DataBox::Null])?);
+        assert!(registry.call("double", &[21.into(), 1.into()]).is_err());
+        assert!(!registry.get("double").unwrap().is_deterministic());
+
+        assert_eq!(
+            DataBox::Integer(1),
+            registry.call("COALESCE", &[DataBox::Null, 1.into(), 2.into()])?
+        );
+        assert!(registry.get("coalesce").unwrap().is_deterministic());
+        assert!(registry.get("upper").unwrap().is_deterministic());
+
+        let err = registry.register_function("Upper", 1, upper).unwrap_err();
+        assert_eq!(
+            Some(&DBError::DuplicateFunctionError("UPPER".to_string())),
+            err.downcast_ref()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_register_aggregate() -> Result<()> {
+        let mut registry = FunctionRegistry::new();
+        registry.register_aggregate("total", || Box::<SumAggregator>::default())?;
+
+        let mut agg = registry.aggregator("Total")?;
+        agg.accumulate(&2.into())?;
+        assert_eq!(DataBox::Long(2), agg.finish()?);
+
+        let mut agg = registry.aggregator("count")?;
+        agg.accumulate(&2.into())?;
+        assert_eq!(DataBox::Long(1), agg.finish()?);
+
+        assert!(registry.aggregator("UPPER").is_err());
+        assert!(registry.call("COUNT", &[1.into()]).is_err());
+        assert!(registry
+            .register_aggregate("upper", || Box::<SumAggregator>::default())
+            .is_err());
+        assert!(registry.register_function("sum", 1, upper).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_registry_errors() {
+        let registry = FunctionRegistry::new();
+
+        let err = registry.call("NOPE", &[]).unwrap_err();
+        assert_eq!(
+            Some(&DBError::UnknownFunctionError("NOPE".to_string())),
+            err.downcast_ref()
+        );
+
+        let err = registry.call("upper", &[]).unwrap_err();
+        assert_eq!(
+            Some(&DBError::ArityError("UPPER".to_string(), 0)),
+            err.downcast_ref()
+        );
+
+        let err = registry.call("UPPER", &[1.into()]).unwrap_err();
+        assert_eq!(
+            Some(&DBError::TypeError(DataBox::Integer(1), "string")),
+            err.downcast_ref()
+        );
+    }
+}
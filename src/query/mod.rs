@@ -0,0 +1,5 @@
+mod aggregate;
+mod function;
+
+pub use aggregate::*;
+pub use function::*;